@@ -0,0 +1,111 @@
+//! Collection benchmark of `/_cat/shards` response of 5000 shards
+//!
+//! `cargo bench --bench collect`
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde_json::Value;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use elasticsearch_exporter::collection::Collection;
+use elasticsearch_exporter::{metric, ExporterOptions};
+
+const INDICES: usize = 500;
+const SHARDS: usize = 5;
+const NODES: usize = 50;
+
+fn cat_shards() -> Vec<Value> {
+    let shards: Vec<Value> =
+        serde_json::from_str(include_str!("../src/tests/files/cat_shards.json"))
+            .expect("valid json");
+
+    let shard = shards
+        .into_iter()
+        .find(|shard| shard["state"] != "RELOCATING")
+        .expect("started shard");
+
+    let mut values = Vec::with_capacity(INDICES * SHARDS * 2);
+
+    for index in 0..INDICES {
+        for number in 0..SHARDS {
+            for prirep in ["p", "r"] {
+                let mut shard = shard.clone();
+                shard["index"] = format!("logs-{:04}", index).into();
+                shard["shard"] = number.to_string().into();
+                shard["prirep"] = prirep.into();
+                shard["node"] = format!("node-{:02}", values.len() % NODES).into();
+                values.push(shard);
+            }
+        }
+    }
+
+    values
+}
+
+fn collection() -> Collection {
+    let mut collection = Collection::new("bench_cat_shards", ExporterOptions::default());
+
+    collection.include_labels = ["index", "node", "shard"].map(String::from).to_vec();
+    collection.skip_labels = ["health", "status", "checkpoint", "prirep"]
+        .map(String::from)
+        .to_vec();
+
+    collection
+}
+
+/// Collecting all shards, flattening is not measured
+fn collect(c: &mut Criterion) {
+    let values = cat_shards();
+
+    let mut group = c.benchmark_group("collect cat_shards x5000");
+    group.sample_size(20);
+
+    // Collection of each iteration is unregistered, else registering same
+    // metrics fails with `AlreadyReg`
+    group.bench_function("first poll (new series)", |b| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+
+            for _ in 0..iters {
+                let mut collection = collection();
+                let metrics = metric::from_values(values.clone());
+
+                let start = Instant::now();
+                for metric in metrics.into_iter() {
+                    let _ = black_box(collection.collect(metric));
+                }
+                elapsed += start.elapsed();
+
+                assert_ne!(collection.series(), 0);
+                collection.unregister();
+            }
+
+            elapsed
+        })
+    });
+
+    let mut collection = collection();
+    for metric in metric::from_values(values.clone()).into_iter() {
+        let _ = collection.collect(metric);
+    }
+    assert_ne!(collection.series(), 0);
+
+    group.bench_function("steady state poll", |b| {
+        b.iter_batched(
+            || metric::from_values(values.clone()),
+            |metrics| {
+                for metric in metrics.into_iter() {
+                    let _ = black_box(collection.collect(metric));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+
+    collection.unregister();
+}
+
+criterion_group!(benches, collect);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+//! Metric flattening benchmark of `/_nodes/stats` response of 300 nodes
+//!
+//! `cargo bench --bench flatten`
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde_json::Value;
+use std::hint::black_box;
+
+use elasticsearch_exporter::metric;
+
+const NODES: usize = 300;
+
+fn nodes_stats() -> Vec<Value> {
+    let stats: Value = serde_json::from_str(include_str!("../src/tests/files/nodes_stats.json"))
+        .expect("valid json");
+
+    let node = stats["nodes"]
+        .as_object()
+        .and_then(|nodes| nodes.values().next())
+        .cloned()
+        .expect("node stats");
+
+    vec![node; NODES]
+}
+
+fn flatten(c: &mut Criterion) {
+    let values = nodes_stats();
+
+    let mut group = c.benchmark_group("flatten nodes_stats x300");
+
+    group.bench_function("from_values (cold keys)", |b| {
+        b.iter_batched(
+            || values.clone(),
+            metric::from_values,
+            BatchSize::LargeInput,
+        )
+    });
+
+    let mut flattener = metric::Flattener::default();
+    group.bench_function("Flattener (interned keys)", |b| {
+        b.iter(|| flattener.flatten(black_box(&values), &[]))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, flatten);
+criterion_main!(benches);
@@ -1,4 +1,5 @@
 use clap::Parser;
+use serde::de::DeserializeOwned;
 use std::error::Error as StdError;
 use std::fmt;
 use std::net::SocketAddr;
@@ -9,7 +10,9 @@ use tokio::sync::oneshot::{self, Receiver, Sender};
 use url::Url;
 
 use elasticsearch_exporter::{
-    CertificateValidationOptions, CollectionLabels, ExporterMetricsSwitch, ExporterPollIntervals,
+    endpoint::EndpointConfig, freshness::FreshnessConfig, metric::MetricRule, query::QueryConfig,
+    CertificateValidationOptions, CollectionLabels, ExporterMaxSeries, ExporterMetricsSwitch,
+    ExporterPollIntervals, MetricTypeRules, PushHeaders, RelabelConfigs,
 };
 
 pub fn unit_channel() -> (Sender<()>, Receiver<()>) {
@@ -83,7 +86,7 @@ pub struct Opts {
     /// https://www.elastic.co/guide/en/elasticsearch/reference/current/cluster-nodes-info.html#cluster-nodes-info-api-path-params
     #[clap(
         long = "elasticsearch_path_parameters",
-        default_value = "nodes_info=http,jvm,thread_pool&nodes_stats=breaker,indices,jvm,os,process,transport,thread_pool"
+        default_value = "nodes_info=http,jvm,thread_pool&nodes_stats=breaker,indexing_pressure,indices,jvm,os,process,transport,thread_pool"
     )]
     pub elasticsearch_path_parameters: HashMapVec,
 
@@ -97,7 +100,7 @@ pub struct Opts {
     /// Exporter include labels
     #[clap(
         long = "exporter_include_labels",
-        default_value = "cat_health=shards&cat_aliases=index,alias&cat_allocation=node&cat_fielddata=node,field&cat_indices=index&cat_nodeattrs=node,attr&cat_nodes=ip,name,node_role&cat_pending_tasks=index,insertorder,timeinqueue,priority,source&cat_plugins=name&cat_recovery=index,shard,stage,type&cat_repositories=index&cat_segments=index,shard&cat_shards=index,node,shard&cat_templates=name,index_patterns&cat_thread_pool=node_name,name,type&cat_transforms=index&cluster_health=status&nodes_usage=name&nodes_stats=name&nodes_info=name&stats=index"
+        default_value = "cat_health=shards&cat_aliases=index,alias&cat_allocation=node&cat_fielddata=node,field&cat_indices=index&cat_nodeattrs=node,attr&cat_nodes=ip,name,node_role&cat_pending_tasks=index,insertorder,timeinqueue,priority,source&cat_plugins=name&cat_recovery=index,shard,stage,type&cat_repositories=index&cat_segments=index,shard&cat_shards=index,node,shard&cat_shards_summary=node,index&cat_templates=name,index_patterns&cat_thread_pool=node_name,name,type&cat_transforms=index&cluster_health=status&cluster_settings=setting,value,source&cluster_watermarks=node&freshness=group,index&index_settings=index&license=type,status&nodes_hot_threads=name,thread_pool,frame&nodes_usage=name&nodes_stats=name,adaptive_selection_target_node,breaker,stage&nodes_info=name&security_authenticate=username,realm,realm_type&ssl_certificates=path,alias,subject_dn&stats=index"
     )]
     pub exporter_include_labels: HashMapVec,
 
@@ -116,6 +119,37 @@ pub struct Opts {
     #[clap(long = "exporter_allow_zero_metrics")]
     pub exporter_allow_zero_metrics: bool,
 
+    /// Exporter metric type rules consulted before built-in rules, glob or regex
+    /// (prefixed with `~`) on metric key followed by type of
+    /// bytes|kilobytes|gauge|float|time_ms|time_us|time_ns|switch|label|drop|counter
+    /// e.g.: `nodes_stats=*_query_cache_cache_size:bytes,~^jvm_.*_percent$:float`
+    #[clap(long = "exporter_metric_type_rules", default_value = "")]
+    pub exporter_metric_type_rules: HashMapRules,
+
+    /// Exporter relabel configs JSON file path, Prometheus style `metric_relabel_configs`
+    /// per subsystem applied to metric key (`__name__`) and labels before metric is registered
+    /// e.g.: `{"nodes_stats": [{"action": "drop", "regex": "jvm_.*"}]}`
+    #[clap(long = "exporter_relabel_config")]
+    pub exporter_relabel_config: Option<RelabelConfigFile>,
+
+    /// Exporter custom search queries JSON file path, each query is polled as
+    /// `query_<name>` subsystem, e.g.:
+    /// `[{"name": "service_docs", "index": "logs-*", "aggs": {"service": {"terms": {"field": "service"}}}}]`
+    #[clap(long = "exporter_query_config")]
+    pub exporter_query_config: Option<JsonConfigFile<QueryConfig>>,
+
+    /// Exporter user defined REST endpoints JSON file path, each endpoint is
+    /// polled as `endpoint_<name>` subsystem, e.g.:
+    /// `[{"name": "ccr_stats", "path": "/_ccr/stats", "list": "follow_stats.indices", "labels": ["index"]}]`
+    #[clap(long = "exporter_endpoint_config")]
+    pub exporter_endpoint_config: Option<JsonConfigFile<EndpointConfig>>,
+
+    /// Exporter index freshness JSON file path, latest document timestamp and
+    /// ingestion lag of each index group is polled as `freshness` subsystem, e.g.:
+    /// `[{"name": "nginx", "index": "logs-nginx-*", "field": "@timestamp", "timeout": "5s"}]`
+    #[clap(long = "exporter_freshness_config")]
+    pub exporter_freshness_config: Option<JsonConfigFile<FreshnessConfig>>,
+
     /// Exporter custom poll intervals for metrics in case custom interval is not
     /// defined it will fall back to default polling interval
     #[clap(long = "exporter_poll_intervals", default_value = "cluster_health=5s")]
@@ -162,6 +196,110 @@ pub struct Opts {
     )]
     pub exporter_metrics_lifetime_interval: HashMapDuration,
 
+    /// Exporter default max labeled series of subsystem, new series are
+    /// dropped once reached, 0 is unlimited
+    #[clap(long = "exporter_max_series_default", default_value = "0")]
+    pub exporter_max_series_default: usize,
+
+    /// Exporter shard store size over which shards are counted by
+    /// `cat_shards_summary` subsystem, e.g.: 50GiB, 30GB
+    #[clap(long = "exporter_shard_size_threshold", default_value = "50GiB")]
+    pub exporter_shard_size_threshold: byte_unit::Byte,
+
+    /// Exporter max labeled series per subsystem, e.g.: `cat_shards=100000`
+    #[clap(long = "exporter_max_series", default_value = "")]
+    pub exporter_max_series: HashMapLimit,
+
+    /// Prometheus remote_write URL, enables push mode of gathered metrics
+    #[clap(long = "exporter_remote_write_url")]
+    pub exporter_remote_write_url: Option<Url>,
+
+    /// Prometheus remote_write push interval
+    #[clap(long = "exporter_remote_write_interval", default_value = "15s")]
+    pub exporter_remote_write_interval: humantime::Duration,
+
+    /// Prometheus remote_write request timeout
+    #[clap(long = "exporter_remote_write_timeout", default_value = "30s")]
+    pub exporter_remote_write_timeout: humantime::Duration,
+
+    /// Prometheus remote_write HTTP headers
+    /// e.g.: `Authorization=Bearer TOKEN&X-Scope-OrgID=tenant`
+    #[clap(long = "exporter_remote_write_headers", default_value = "")]
+    pub exporter_remote_write_headers: HashMapHeaders,
+
+    /// Prometheus remote_write in-memory queue size of payloads pending retry,
+    /// oldest payload is dropped when queue is full
+    #[clap(long = "exporter_remote_write_queue_size", default_value = "64")]
+    pub exporter_remote_write_queue_size: usize,
+
+    /// Prometheus remote_write retries of a payload before it is retried on next interval
+    #[clap(long = "exporter_remote_write_max_retries", default_value = "3")]
+    pub exporter_remote_write_max_retries: usize,
+
+    /// OpenTelemetry OTLP/HTTP metrics URL, e.g.: http://localhost:4318/v1/metrics,
+    /// enables OTLP export of gathered metrics
+    #[clap(long = "exporter_otlp_url")]
+    pub exporter_otlp_url: Option<Url>,
+
+    /// OTLP export interval
+    #[clap(long = "exporter_otlp_interval", default_value = "15s")]
+    pub exporter_otlp_interval: humantime::Duration,
+
+    /// OTLP request timeout
+    #[clap(long = "exporter_otlp_timeout", default_value = "30s")]
+    pub exporter_otlp_timeout: humantime::Duration,
+
+    /// OTLP HTTP headers
+    /// e.g.: `Authorization=Bearer TOKEN&X-Scope-OrgID=tenant`
+    #[clap(long = "exporter_otlp_headers", default_value = "")]
+    pub exporter_otlp_headers: HashMapHeaders,
+
+    /// OTLP service.instance.id resource attribute, defaults to hostname
+    #[clap(long = "exporter_otlp_instance")]
+    pub exporter_otlp_instance: Option<String>,
+
+    /// Exporter subsystems exported with explicit sample timestamp of poll completion,
+    /// e.g.: `cat_segments=true&cat_shards=true`
+    #[clap(long = "exporter_sample_timestamps", default_value = "")]
+    pub exporter_sample_timestamps: HashMapSwitch,
+
+    /// Collect enabled subsystems concurrently on scrape instead of polling on interval
+    #[clap(long = "exporter_on_demand")]
+    pub exporter_on_demand: bool,
+
+    /// Minimum interval between on-demand collections of a subsystem,
+    /// overlapping scrapes within interval are served from cache
+    #[clap(long = "exporter_on_demand_min_interval", default_value = "5s")]
+    pub exporter_on_demand_min_interval: humantime::Duration,
+
+    /// DogStatsD UDP address, e.g.: 127.0.0.1:8125, enables StatsD sink of collected metrics
+    #[clap(long = "exporter_statsd_addr")]
+    pub exporter_statsd_addr: Option<String>,
+
+    /// DogStatsD metric name prefix
+    #[clap(long = "exporter_statsd_prefix", default_value = "elasticsearch")]
+    pub exporter_statsd_prefix: String,
+
+    /// DogStatsD sink flush interval
+    #[clap(long = "exporter_statsd_flush_interval", default_value = "10s")]
+    pub exporter_statsd_flush_interval: humantime::Duration,
+
+    /// Graphite plaintext TCP address, e.g.: 127.0.0.1:2003, enables Graphite sink of collected metrics
+    #[clap(long = "exporter_graphite_addr")]
+    pub exporter_graphite_addr: Option<String>,
+
+    /// Graphite metric path prefix
+    #[clap(long = "exporter_graphite_prefix", default_value = "elasticsearch")]
+    pub exporter_graphite_prefix: String,
+
+    /// Graphite sink flush interval
+    #[clap(long = "exporter_graphite_flush_interval", default_value = "60s")]
+    pub exporter_graphite_flush_interval: humantime::Duration,
+
+    /// Disable /metrics endpoint, e.g.: metrics are only pushed via remote_write, OTLP or sinks
+    #[clap(long = "exporter_disable_metrics_endpoint")]
+    pub exporter_disable_metrics_endpoint: bool,
+
     /// Exporter certificate path for Elasticsearch client
     pub elasticsearch_certificate_path: Option<PathBuf>,
 
@@ -186,6 +324,22 @@ impl FromStr for HashMapSwitch {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct HashMapLimit(pub ExporterMaxSeries);
+
+impl FromStr for HashMapLimit {
+    type Err = SimpleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(Self(serde_qs::from_str(input).map_err(|e| {
+            SimpleError(format!(
+                "Usage `cat_shards=100000&cat_indices=50000`, you provided `{}`",
+                e
+            ))
+        })?))
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct HashMapDuration(pub ExporterPollIntervals);
 
@@ -223,6 +377,163 @@ impl FromStr for HashMapDuration {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct HashMapRules(pub MetricTypeRules);
+
+impl FromStr for HashMapRules {
+    type Err = SimpleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut map = MetricTypeRules::new();
+
+        for (subsystem, rules) in HashMapVec::from_str(input)?.0.into_iter() {
+            let rules = rules
+                .iter()
+                .map(|rule| rule.parse::<MetricRule>())
+                .collect::<Result<Vec<MetricRule>, _>>()
+                .map_err(|e| SimpleError(format!("Subsystem {} {}", subsystem, e)))?;
+
+            let _ = map.insert(subsystem, rules);
+        }
+
+        Ok(Self(map))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HashMapHeaders(pub PushHeaders);
+
+impl FromStr for HashMapHeaders {
+    type Err = SimpleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut map = PushHeaders::new();
+
+        if input.is_empty() {
+            return Ok(Self(map));
+        }
+
+        for part in input.trim().split('&') {
+            match part.split_once('=') {
+                Some((key, value)) => {
+                    let _ = map.insert(key.trim().to_string(), value.trim().to_string());
+                }
+                None => {
+                    return Err(SimpleError(format!(
+                    "Usage `Authorization=Bearer TOKEN&X-Scope-OrgID=tenant`, you provided `{}`",
+                    part
+                )))
+                }
+            }
+        }
+
+        Ok(Self(map))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RelabelConfigFile(pub RelabelConfigs);
+
+impl FromStr for RelabelConfigFile {
+    type Err = SimpleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let file = std::fs::read_to_string(input).map_err(|e| {
+            SimpleError(format!("Failed to read relabel config {} err {}", input, e))
+        })?;
+
+        Ok(Self(serde_json::from_str(&file).map_err(|e| {
+            SimpleError(format!(
+                "Usage `{{\"nodes_stats\": [{{\"action\": \"drop\", \"regex\": \"jvm_.*\"}}]}}`, relabel config {} err {}",
+                input, e
+            ))
+        })?))
+    }
+}
+
+/// Named entry of JSON config file list, names must be unique
+pub trait JsonConfig: DeserializeOwned {
+    /// Config kind used in errors, e.g.: "query"
+    const KIND: &'static str;
+    /// Config file usage example
+    const USAGE: &'static str;
+
+    /// Unique name of entry
+    fn name(&self) -> &str;
+}
+
+impl JsonConfig for QueryConfig {
+    const KIND: &'static str = "query";
+    const USAGE: &'static str = r#"[{"name": "service_docs", "index": "logs-*"}]"#;
+
+    fn name(&self) -> &str {
+        self.subsystem()
+    }
+}
+
+impl JsonConfig for EndpointConfig {
+    const KIND: &'static str = "endpoint";
+    const USAGE: &'static str = r#"[{"name": "ccr_stats", "path": "/_ccr/stats"}]"#;
+
+    fn name(&self) -> &str {
+        self.subsystem()
+    }
+}
+
+impl JsonConfig for FreshnessConfig {
+    const KIND: &'static str = "freshness";
+    const USAGE: &'static str = r#"[{"name": "nginx", "index": "logs-nginx-*"}]"#;
+
+    fn name(&self) -> &str {
+        FreshnessConfig::name(self)
+    }
+}
+
+/// JSON file of config list
+#[derive(Clone, Debug)]
+pub struct JsonConfigFile<T>(pub Vec<T>);
+
+impl<T: JsonConfig> FromStr for JsonConfigFile<T> {
+    type Err = SimpleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let file = std::fs::read_to_string(input).map_err(|e| {
+            SimpleError(format!(
+                "Failed to read {} config {} err {}",
+                T::KIND,
+                input,
+                e
+            ))
+        })?;
+
+        let configs: Vec<T> = serde_json::from_str(&file).map_err(|e| {
+            SimpleError(format!(
+                "Usage `{}`, {} config {} err {}",
+                T::USAGE,
+                T::KIND,
+                input,
+                e
+            ))
+        })?;
+
+        for (index, config) in configs.iter().enumerate() {
+            if configs[..index]
+                .iter()
+                .any(|other| other.name() == config.name())
+            {
+                return Err(SimpleError(format!(
+                    "Duplicate {} name {} in config {}",
+                    T::KIND,
+                    config.name(),
+                    input
+                )));
+            }
+        }
+
+        Ok(Self(configs))
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct HashMapVec(pub CollectionLabels);
 
@@ -7,17 +7,20 @@ extern crate log;
 
 use clap::Parser;
 use hyper::{
-    header::CONTENT_TYPE,
+    header::{ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, VARY},
     server::conn::AddrStream,
     service::{make_service_fn, service_fn},
     Body, Request, Response, Server, StatusCode,
 };
-use prometheus::{Encoder, HistogramVec, TextEncoder, TEXT_FORMAT};
+use prometheus::{HistogramVec, TEXT_FORMAT};
 use std::convert::Infallible;
 use std::env;
 use std::panic;
 
-use elasticsearch_exporter::{Exporter, ExporterOptions};
+use elasticsearch_exporter::{collection::registry, exposition, Exporter, ExporterOptions};
+
+/// Metric keys listed by series dropped debug endpoint
+const SERIES_DROPPED_TOP: usize = 100;
 
 lazy_static! {
     static ref HTTP_REQ_HISTOGRAM: HistogramVec = register_histogram_vec!(
@@ -36,7 +39,69 @@ fn build_response(status: StatusCode, body: Body) -> Response<Body> {
         .expect("valid Response built")
 }
 
-async fn serve_req(req: Request<Body>, options: String) -> Result<Response<Body>, Infallible> {
+fn metrics_response(format: exposition::Format, gzip: bool, buffer: Vec<u8>) -> Response<Body> {
+    let builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, format.content_type())
+        .header(VARY, "Accept, Accept-Encoding");
+
+    let response = if gzip {
+        match exposition::gzip(&buffer) {
+            Ok(compressed) => builder
+                .header(CONTENT_ENCODING, "gzip")
+                .body(Body::from(compressed)),
+            Err(e) => {
+                error!("gzip err {}", e);
+
+                builder.body(Body::from(buffer))
+            }
+        }
+    } else {
+        builder.body(Body::from(buffer))
+    };
+
+    response.expect("valid Response built")
+}
+
+/// Subsystems of `collect[]` query parameters, e.g.:
+/// `collect[]=nodes_stats&collect[]=cat_health`
+fn collect_query(query: Option<&str>) -> Vec<String> {
+    url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .filter(|(key, _)| key == "collect[]")
+        .map(|(_, value)| value.into_owned())
+        .collect()
+}
+
+/// Encode metric families in format negotiated by request headers
+fn encode_metrics(
+    req: &Request<Body>,
+    exporter: &Exporter,
+    mut families: Vec<prometheus::proto::MetricFamily>,
+) -> Response<Body> {
+    exporter.set_sample_timestamps(&mut families);
+
+    let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    let format = exposition::Format::negotiate(header(ACCEPT));
+    let gzip = exposition::accepts_gzip(header(ACCEPT_ENCODING));
+
+    let mut buffer = vec![];
+    match format.encode(&families, &mut buffer) {
+        Ok(_) => metrics_response(format, gzip, buffer),
+        Err(e) => {
+            error!("prometheus encoder err {}", e);
+
+            build_response(StatusCode::INTERNAL_SERVER_ERROR, Body::empty())
+        }
+    }
+}
+
+async fn serve_req(
+    req: Request<Body>,
+    options: String,
+    metrics_endpoint: bool,
+    exporter: Exporter,
+) -> Result<Response<Body>, Infallible> {
     let path = req.uri().path();
 
     let timer = HTTP_REQ_HISTOGRAM.with_label_values(&[path]).start_timer();
@@ -45,19 +110,49 @@ async fn serve_req(req: Request<Body>, options: String) -> Result<Response<Body>
         "/health" | "/healthy" | "/healthz" => build_response(StatusCode::OK, Body::from("Ok")),
         "/" => build_response(StatusCode::OK, Body::from(options)),
 
-        "/metrics" => {
-            let encoder = TextEncoder::new();
+        "/metrics" if metrics_endpoint => {
+            let collect = collect_query(req.uri().query());
 
-            let mut buffer = vec![];
-            match encoder.encode(&prometheus::gather(), &mut buffer) {
-                Ok(_) => build_response(StatusCode::OK, Body::from(buffer)),
-                Err(e) => {
-                    error!("prometheus encoder err {}", e);
+            exporter
+                .collect(|subsystem| {
+                    collect.is_empty() || collect.iter().any(|collect| collect == subsystem)
+                })
+                .await;
 
-                    build_response(StatusCode::INTERNAL_SERVER_ERROR, Body::empty())
+            if collect.is_empty() {
+                encode_metrics(&req, &exporter, prometheus::gather())
+            } else {
+                match registry::gather(&collect.iter().map(String::as_str).collect::<Vec<&str>>()) {
+                    Ok(families) => encode_metrics(&req, &exporter, families),
+                    Err(subsystem) => build_response(
+                        StatusCode::BAD_REQUEST,
+                        Body::from(format!("Subsystem {} not found", subsystem)),
+                    ),
                 }
             }
         }
+        "/debug/series_dropped" => {
+            let mut output = String::from("subsystem\tmetric\tdropped");
+
+            for (subsystem, key, dropped) in exporter.series_dropped_top(SERIES_DROPPED_TOP) {
+                output.push_str(&format!("\n{}\t{}\t{}", subsystem, key, dropped));
+            }
+
+            build_response(StatusCode::OK, Body::from(output))
+        }
+        path if metrics_endpoint && path.starts_with("/metrics/") => {
+            let subsystem = path.trim_start_matches("/metrics/");
+
+            exporter.collect(|collect| collect == subsystem).await;
+
+            match registry::gather(&[subsystem]) {
+                Ok(families) => encode_metrics(&req, &exporter, families),
+                Err(subsystem) => build_response(
+                    StatusCode::NOT_FOUND,
+                    Body::from(format!("Subsystem {} not found", subsystem)),
+                ),
+            }
+        }
         _ => build_response(
             StatusCode::NOT_FOUND,
             Body::from(format!("Path {} not found", path)),
@@ -109,6 +204,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         exporter_include_labels: opts.exporter_include_labels.0.clone(),
         exporter_poll_default_interval: *opts.exporter_poll_default_interval,
         exporter_skip_zero_metrics: !opts.exporter_allow_zero_metrics,
+        exporter_metric_type_rules: opts.exporter_metric_type_rules.0.clone(),
+        exporter_relabel_configs: opts
+            .exporter_relabel_config
+            .as_ref()
+            .map(|config| config.0.clone())
+            .unwrap_or_default(),
         exporter_poll_intervals: opts.exporter_poll_intervals.0.clone(),
         exporter_metrics_enabled: opts.exporter_metrics_enabled.0.clone(),
         exporter_metrics_namespace: opts.exporter_metrics_namespace.clone(),
@@ -117,6 +218,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         exporter_metrics_lifetime_interval: opts.exporter_metrics_lifetime_interval.0.clone(),
         exporter_metrics_lifetime_default_interval: *opts
             .exporter_metrics_lifetime_default_interval,
+        exporter_queries: opts
+            .exporter_query_config
+            .as_ref()
+            .map(|config| config.0.clone())
+            .unwrap_or_default(),
+        exporter_endpoints: opts
+            .exporter_endpoint_config
+            .as_ref()
+            .map(|config| config.0.clone())
+            .unwrap_or_default(),
+        exporter_freshness: opts
+            .exporter_freshness_config
+            .as_ref()
+            .map(|config| config.0.clone())
+            .unwrap_or_default(),
+        exporter_max_series: opts.exporter_max_series.0.clone(),
+        exporter_max_series_default: opts.exporter_max_series_default,
+        exporter_shard_size_threshold: opts.exporter_shard_size_threshold.as_u64(),
+
+        exporter_remote_write_url: opts.exporter_remote_write_url.clone(),
+        exporter_remote_write_interval: *opts.exporter_remote_write_interval,
+        exporter_remote_write_timeout: *opts.exporter_remote_write_timeout,
+        exporter_remote_write_headers: opts.exporter_remote_write_headers.0.clone(),
+        exporter_remote_write_queue_size: opts.exporter_remote_write_queue_size,
+        exporter_remote_write_max_retries: opts.exporter_remote_write_max_retries,
+
+        exporter_otlp_url: opts.exporter_otlp_url.clone(),
+        exporter_otlp_interval: *opts.exporter_otlp_interval,
+        exporter_otlp_timeout: *opts.exporter_otlp_timeout,
+        exporter_otlp_headers: opts.exporter_otlp_headers.0.clone(),
+        exporter_otlp_instance: opts.exporter_otlp_instance.clone(),
+
+        exporter_sample_timestamps: opts.exporter_sample_timestamps.0.clone(),
+
+        exporter_on_demand: opts.exporter_on_demand,
+        exporter_on_demand_min_interval: *opts.exporter_on_demand_min_interval,
+
+        exporter_statsd_addr: opts.exporter_statsd_addr.clone(),
+        exporter_statsd_prefix: opts.exporter_statsd_prefix.clone(),
+        exporter_statsd_flush_interval: *opts.exporter_statsd_flush_interval,
+        exporter_graphite_addr: opts.exporter_graphite_addr.clone(),
+        exporter_graphite_prefix: opts.exporter_graphite_prefix.clone(),
+        exporter_graphite_flush_interval: *opts.exporter_graphite_flush_interval,
 
         elasticsearch_certificate_path: opts.elasticsearch_certificate_path.clone(),
         elasticsearch_certificate_validation: opts.elasticsearch_certificate_validation,
@@ -124,28 +268,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("{}", options);
 
-    let options_clone = options.clone();
-    let new_service = make_service_fn(move |socket: &AddrStream| {
-        let options_string = options_clone.to_string();
-
-        let svc = service_fn(move |req| serve_req(req, options_string.clone()));
-        trace!("incoming socket request: {:?}", socket);
-        async move { Ok::<_, Infallible>(svc) }
-    });
-
     let signal_rx = signal_channel();
 
-    match Exporter::new(options).await {
+    let exporter = match Exporter::new(options).await {
         Ok(exporter) => {
             #[allow(clippy::let_underscore_future)]
-            let _ = tokio::spawn(exporter.spawn());
+            let _ = tokio::spawn(exporter.clone().spawn());
+
+            exporter
         }
         Err(e) => {
             error!("{}", e);
 
             std::process::exit(70);
         }
-    }
+    };
+
+    let metrics_endpoint = !opts.exporter_disable_metrics_endpoint;
+    let new_service = make_service_fn(move |socket: &AddrStream| {
+        let options_string = exporter.options().to_string();
+        let exporter = exporter.clone();
+
+        let svc = service_fn(move |req| {
+            serve_req(
+                req,
+                options_string.clone(),
+                metrics_endpoint,
+                exporter.clone(),
+            )
+        });
+        trace!("incoming socket request: {:?}", socket);
+        async move { Ok::<_, Infallible>(svc) }
+    });
 
     info!("Listening on http://{}", opts.listen_addr);
 
@@ -0,0 +1,78 @@
+use prometheus::core::{Collector, Desc};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use super::lifetime;
+
+/// Created timestamps of counter and histogram series used for OpenMetrics
+/// `_created` samples, keyed by hash of metric name and label values
+static CREATED: LazyLock<RwLock<HashMap<u64, f64>>> = LazyLock::new(Default::default);
+
+/// Hash of metric name and all label values (constant and variable) ordered
+/// by label name, the same order labels are encoded in
+fn series_hash(desc: &Desc, label_values: &[&str]) -> u64 {
+    let mut labels: Vec<(&str, &str)> = desc
+        .const_label_pairs
+        .iter()
+        .map(|pair| (pair.get_name(), pair.get_value()))
+        .chain(
+            desc.variable_labels
+                .iter()
+                .map(String::as_str)
+                .zip(label_values.iter().copied()),
+        )
+        .collect();
+    labels.sort_unstable_by_key(|(name, _)| *name);
+
+    lifetime::hash_label(
+        &desc.fq_name,
+        &labels
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<&str>>(),
+    )
+}
+
+/// Record series creation time unless already recorded, `reset` overrides
+/// existing creation time, e.g.: counter reset after node restart
+pub(crate) fn set(
+    collector: &impl Collector,
+    label_values: &[&str],
+    now: chrono::DateTime<chrono::Utc>,
+    reset: bool,
+) {
+    let Some(desc) = collector.desc().into_iter().next() else {
+        return;
+    };
+
+    let hash = series_hash(desc, label_values);
+    let created = now.timestamp_millis() as f64 / 1000.0;
+
+    let mut map = CREATED.write().expect("created lock");
+
+    if reset {
+        let _ = map.insert(hash, created);
+    } else {
+        let _ = map.entry(hash).or_insert(created);
+    }
+}
+
+/// Forget creation time of removed series
+pub(crate) fn remove(collector: &impl Collector, label_values: &[&str]) {
+    if let Some(desc) = collector.desc().into_iter().next() {
+        let _ = CREATED
+            .write()
+            .expect("created lock")
+            .remove(&series_hash(desc, label_values));
+    }
+}
+
+/// Creation time in seconds since epoch of series by metric name and all
+/// label values ordered by label name
+pub fn get(fq_name: &str, label_values: &[&str]) -> Option<f64> {
+    CREATED
+        .read()
+        .expect("created lock")
+        .get(&lifetime::hash_label(fq_name, label_values))
+        .copied()
+}
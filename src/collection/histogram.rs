@@ -0,0 +1,173 @@
+use prometheus::core::{Collector, Desc, Describer};
+use prometheus::proto::{self, MetricFamily};
+use prometheus::Opts;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::metric::Histogram;
+
+/// Histogram vector which buckets are set from Elasticsearch snapshot
+/// instead of being observed, prometheus::HistogramVec only supports observations
+#[derive(Debug, Clone)]
+pub struct SnapshotHistogramVec {
+    desc: Desc,
+    histograms: Arc<RwLock<HashMap<Vec<String>, Histogram>>>,
+}
+
+impl SnapshotHistogramVec {
+    /// Create new histogram vector with given label names
+    pub fn new(opts: Opts, label_names: &[&str]) -> Result<Self, prometheus::Error> {
+        let desc = opts
+            .variable_labels(label_names.iter().map(|s| s.to_string()).collect())
+            .describe()?;
+
+        Ok(Self {
+            desc,
+            histograms: Default::default(),
+        })
+    }
+
+    /// Replace histogram of given label values
+    pub fn set(
+        &self,
+        label_values: &[&str],
+        histogram: Histogram,
+    ) -> Result<(), prometheus::Error> {
+        self.check_cardinality(label_values)?;
+
+        let _ = self.histograms.write().expect("histogram lock").insert(
+            label_values.iter().map(|s| s.to_string()).collect(),
+            histogram,
+        );
+
+        Ok(())
+    }
+
+    /// Remove histogram of given label values
+    pub fn remove_label_values(&self, label_values: &[&str]) -> Result<(), prometheus::Error> {
+        self.check_cardinality(label_values)?;
+
+        let key = label_values
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+
+        match self
+            .histograms
+            .write()
+            .expect("histogram lock")
+            .remove(&key)
+        {
+            Some(_) => Ok(()),
+            None => Err(prometheus::Error::Msg(format!(
+                "missing label values {:?}",
+                label_values
+            ))),
+        }
+    }
+
+    fn check_cardinality(&self, label_values: &[&str]) -> Result<(), prometheus::Error> {
+        if label_values.len() != self.desc.variable_labels.len() {
+            return Err(prometheus::Error::InconsistentCardinality {
+                expect: self.desc.variable_labels.len(),
+                got: label_values.len(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Collector for SnapshotHistogramVec {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&self.desc]
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let mut family = MetricFamily::default();
+        family.set_name(self.desc.fq_name.clone());
+        family.set_help(self.desc.help.clone());
+        family.set_field_type(proto::MetricType::HISTOGRAM);
+
+        for (label_values, histogram) in self.histograms.read().expect("histogram lock").iter() {
+            let mut metric = proto::Metric::default();
+
+            let mut labels = self.desc.const_label_pairs.clone();
+            for (name, value) in self.desc.variable_labels.iter().zip(label_values.iter()) {
+                let mut label = proto::LabelPair::default();
+                label.set_name(name.clone());
+                label.set_value(value.clone());
+                labels.push(label);
+            }
+            labels.sort();
+
+            for label in labels.into_iter() {
+                metric.mut_label().push(label);
+            }
+
+            let mut proto_histogram = proto::Histogram::default();
+            proto_histogram.set_sample_count(histogram.count());
+            proto_histogram.set_sample_sum(histogram.sum());
+
+            for (upper_bound, cumulative_count) in histogram.buckets().iter() {
+                let mut bucket = proto::Bucket::default();
+                bucket.set_upper_bound(*upper_bound);
+                bucket.set_cumulative_count(*cumulative_count);
+                proto_histogram.mut_bucket().push(bucket);
+            }
+
+            metric.set_histogram(proto_histogram);
+            family.mut_metric().push(metric);
+        }
+
+        vec![family]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{Encoder, Registry, TextEncoder};
+    use serde_json::Value;
+
+    #[test]
+    fn test_snapshot_histogram_encode() {
+        let buckets: Value =
+            serde_json::from_str(r#"[{"lt_millis": 1, "count": 2}, {"ge_millis": 1, "count": 1}]"#)
+                .unwrap();
+        let histogram = Histogram::from_buckets(buckets.as_array().unwrap()).unwrap();
+
+        let vec = SnapshotHistogramVec::new(
+            Opts::new("handling_time_seconds", "handling_time").namespace("elasticsearch"),
+            &["name"],
+        )
+        .unwrap();
+        vec.set(&["node"], histogram).unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(vec.clone())).unwrap();
+
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut buffer)
+            .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        assert!(output.contains("# TYPE elasticsearch_handling_time_seconds histogram"));
+        assert!(output
+            .contains("elasticsearch_handling_time_seconds_bucket{name=\"node\",le=\"0.001\"} 2"));
+        assert!(output
+            .contains("elasticsearch_handling_time_seconds_bucket{name=\"node\",le=\"+Inf\"} 3"));
+        assert!(output.contains("elasticsearch_handling_time_seconds_count{name=\"node\"} 3"));
+        // Lower bound of the open ended bucket
+        assert!(output.contains("elasticsearch_handling_time_seconds_sum{name=\"node\"} 0.001"));
+
+        assert!(vec.remove_label_values(&["node"]).is_ok());
+        assert!(vec
+            .set(
+                &[],
+                Histogram::from_buckets(buckets.as_array().unwrap()).unwrap()
+            )
+            .is_err());
+    }
+}
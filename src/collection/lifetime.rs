@@ -3,6 +3,8 @@ use fnv::FnvHasher;
 use std::collections::HashMap;
 use std::hash::Hasher;
 
+use crate::Labels;
+
 pub(crate) fn hash_label(key: &str, values: &[&str]) -> u64 {
     let mut h = FnvHasher::default();
     h.write(key.as_bytes());
@@ -14,6 +16,19 @@ pub(crate) fn hash_label(key: &str, values: &[&str]) -> u64 {
     h.finish()
 }
 
+/// Hash of metric key and label values equal to [`hash_label`] of the same
+/// values without collecting label values first
+pub(crate) fn hash_labels(key: &str, labels: &Labels) -> u64 {
+    let mut h = FnvHasher::default();
+    h.write(key.as_bytes());
+
+    for val in labels.values() {
+        h.write(val.as_bytes());
+    }
+
+    h.finish()
+}
+
 /// Return current date time
 pub fn now() -> DateTime<Utc> {
     Utc::now()
@@ -48,8 +63,42 @@ impl MetricLifetime {
         self
     }
 
+    /// Check if lifetime belongs to metric with given labels, distinguishes
+    /// series with colliding label hash
+    pub fn is_series(&self, metric_key: &str, labels: &Labels) -> bool {
+        self.metric_key == metric_key
+            && self.label_values.len() == labels.len()
+            && self
+                .label_values
+                .iter()
+                .zip(labels.values())
+                .all(|(a, b)| a == b)
+    }
+
     /// Check if metric is outdated based on last metric heartbeat
     pub fn is_outdated(&self, date: DateTime<Utc>) -> bool {
         self.last_hearbeat < date
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_labels() {
+        let mut labels = Labels::new();
+        let _ = labels.insert("index".into(), "logs".into());
+        let _ = labels.insert("cluster".into(), "testing".into());
+
+        assert_eq!(
+            hash_labels("docs", &labels),
+            hash_label("docs", &["testing", "logs"])
+        );
+
+        let lifetime = MetricLifetime::new("docs".into(), vec!["testing".into(), "logs".into()]);
+        assert!(lifetime.is_series("docs", &labels));
+        assert!(!lifetime.is_series("store", &labels));
+        assert!(!lifetime.is_series("docs", &Labels::new()));
+    }
+}
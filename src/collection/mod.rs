@@ -1,13 +1,27 @@
-use prometheus::{default_registry, GaugeVec, IntGaugeVec, Opts};
+use prometheus::{Gauge, GaugeVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts};
 use std::collections::HashMap;
+use std::sync::Arc;
 
+/// Created timestamps of counters and histograms
+pub mod created;
+/// Histograms set from Elasticsearch bucketed arrays
+pub mod histogram;
 /// Lifetime of a metric based on heartbeat
 pub mod lifetime;
+/// Per subsystem registries
+pub mod registry;
+/// Prometheus style relabeling of metric keys and labels
+pub mod relabel;
 
 use crate::{
-    metric::{Metric, MetricType},
+    metric::{catalog, Histogram, Metric, MetricType},
+    sink::{Sample, Sink},
     ExporterOptions, Labels,
 };
+use histogram::SnapshotHistogramVec;
+
+/// Elasticsearch does not report sum of observations
+const HISTOGRAM_SUM_HELP: &str = " (_sum is lower bound estimate from bucket bounds)";
 
 /// Generic collector of metrics
 #[derive(Debug)]
@@ -16,6 +30,10 @@ pub struct Collection {
     pub gauges: HashMap<String, IntGaugeVec>,
     /// Float gauges of collection
     pub fgauges: HashMap<String, GaugeVec>,
+    /// Integer counters of collection
+    pub counters: HashMap<String, IntCounterVec>,
+    /// Histograms of collection
+    pub histograms: HashMap<String, SnapshotHistogramVec>,
     subsystem: &'static str,
     /// Remove metrics from registry
     pub skip_metrics: Vec<String>,
@@ -25,18 +43,39 @@ pub struct Collection {
     pub include_labels: Vec<String>,
     /// Constant metric labels
     pub const_labels: HashMap<String, String>,
+    /// Relabel configs applied before metric is registered
+    pub relabel_configs: Vec<relabel::RelabelConfig>,
+    /// Sinks collected metrics are emitted to besides Prometheus registry
+    pub sinks: Vec<Arc<dyn Sink>>,
+    /// Maximum labeled series of collection, new series are dropped once
+    /// reached, 0 is unlimited
+    pub max_series: usize,
+    /// Dropped new series by metric key since last taken
+    dropped: HashMap<String, u64>,
     /// Exporter options
     options: ExporterOptions,
     /// Metric lifetime is used to remove stale metrics
     pub gauges_lifetime: lifetime::MetricLifetimeMap,
     /// Metric lifetime is used to remove stale metrics
     pub fgauges_lifetime: lifetime::MetricLifetimeMap,
+    /// Metric lifetime is used to remove stale metrics
+    pub counters_lifetime: lifetime::MetricLifetimeMap,
+    /// Metric lifetime is used to remove stale metrics
+    pub histograms_lifetime: lifetime::MetricLifetimeMap,
+    /// Integer gauge series by lifetime hash, removed with their lifetime
+    pub gauges_series: HashMap<u64, IntGauge>,
+    /// Float gauge series by lifetime hash, removed with their lifetime
+    pub fgauges_series: HashMap<u64, Gauge>,
+    /// Integer counter series by lifetime hash, removed with their lifetime
+    pub counters_series: HashMap<u64, IntCounter>,
 }
 
 impl Collection {
     /// Initialize collection with given exporter options and subsystem,
     /// such as: cat_indices, cat_shards, etc.
     pub fn new(subsystem: &'static str, options: ExporterOptions) -> Self {
+        registry::init(subsystem);
+
         Self {
             subsystem,
             options,
@@ -44,30 +83,139 @@ impl Collection {
             skip_labels: vec![],
             include_labels: vec![],
             const_labels: HashMap::new(),
+            relabel_configs: vec![],
+            sinks: vec![],
+            max_series: 0,
+            dropped: HashMap::new(),
             gauges: HashMap::new(),
             fgauges: HashMap::new(),
+            counters: HashMap::new(),
+            histograms: HashMap::new(),
             gauges_lifetime: Default::default(),
             fgauges_lifetime: Default::default(),
+            counters_lifetime: Default::default(),
+            histograms_lifetime: Default::default(),
+            gauges_series: HashMap::new(),
+            fgauges_series: HashMap::new(),
+            counters_series: HashMap::new(),
         }
     }
 
-    /// Insert Gauge type metric into collection
-    pub fn insert_fgauge(
+    /// Insert Counter type metric into collection, Elasticsearch reports
+    /// counter totals thus counter is incremented by difference since last
+    /// poll or reset in case total decreased (e.g.: node restart)
+    ///
+    /// Returns whether sample was accepted, sample is rejected once max
+    /// series is reached or skipped while metric is zero
+    pub fn insert_counter(
         &mut self,
         key: &str,
-        value: f64,
+        value: i64,
         labels: &Labels,
-        key_postfix: Option<&'static str>,
         skippable: bool,
         now: chrono::DateTime<chrono::Utc>,
-    ) -> Result<(), prometheus::Error> {
-        let set_labels = |gauge: &GaugeVec,
+    ) -> Result<bool, prometheus::Error> {
+        let set_labels = |vec: &IntCounterVec,
+                          series: &mut HashMap<u64, IntCounter>,
+                          lifetime: &mut lifetime::MetricLifetimeMap|
+         -> Result<(), prometheus::Error> {
+            let value = value.max(0) as u64;
+
+            with_series(
+                series,
+                lifetime,
+                key,
+                labels,
+                now,
+                |label_values| vec.get_metric_with_label_values(label_values),
+                |counter, resolved| {
+                    let current = counter.get();
+                    let reset = value < current;
+
+                    if reset {
+                        counter.reset();
+                        counter.inc_by(value);
+                    } else {
+                        counter.inc_by(value - current);
+                    }
+
+                    if resolved || reset {
+                        // BTreeMap ensures that values returned are always sorted
+                        let label_values =
+                            &labels.values().map(|s| s.as_str()).collect::<Vec<&str>>();
+                        created::set(vec, label_values, now, reset);
+                    }
+                },
+            )
+        };
+
+        if let Some(counter) = self.counters.get(key) {
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
+            }
+
+            set_labels(
+                counter,
+                &mut self.counters_series,
+                &mut self.counters_lifetime,
+            )?;
+        } else {
+            // If metric is skippable and haven't been registered skip it
+            // until value is not zero
+            if skippable && self.options.exporter_skip_zero_metrics && value == 0 {
+                return Ok(false);
+            }
+
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
+            }
+
+            let mut metric_key = key.to_string();
+
+            if !key.ends_with("_total") {
+                metric_key.push_str("_total");
+            }
+
+            let new_counter = IntCounterVec::new(
+                self.opts(metric_key, key),
+                &labels.keys().map(|s| s.as_str()).collect::<Vec<&str>>(),
+            )?;
+
+            set_labels(
+                &new_counter,
+                &mut self.counters_series,
+                &mut self.counters_lifetime,
+            )?;
+
+            // Register new metric
+            registry::register(self.subsystem, &new_counter)?;
+
+            let _ = self.counters.insert(key.to_string(), new_counter);
+        }
+
+        Ok(true)
+    }
+
+    /// Insert Histogram type metric into collection, returns whether sample
+    /// was accepted
+    pub fn insert_histogram(
+        &mut self,
+        key: &str,
+        histogram: &Histogram,
+        labels: &Labels,
+        skippable: bool,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, prometheus::Error> {
+        let set_labels = |vec: &SnapshotHistogramVec,
                           lifetime: &mut lifetime::MetricLifetimeMap|
          -> Result<(), prometheus::Error> {
             // BTreeMap ensures that values returned are always sorted
             let label_values = &labels.values().map(|s| s.as_str()).collect::<Vec<&str>>();
 
-            gauge.get_metric_with_label_values(label_values)?.set(value);
+            vec.set(label_values, histogram.clone())?;
+            created::set(vec, label_values, now, false);
 
             if !label_values.is_empty() {
                 let _ = lifetime
@@ -84,14 +232,88 @@ impl Collection {
             Ok(())
         };
 
+        if let Some(vec) = self.histograms.get(key) {
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
+            }
+
+            set_labels(vec, &mut self.histograms_lifetime)?;
+        } else {
+            // If metric is skippable and haven't been registered skip it
+            // until there are observations
+            if skippable && self.options.exporter_skip_zero_metrics && histogram.count() == 0 {
+                return Ok(false);
+            }
+
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
+            }
+
+            let mut opts = self.opts(key.to_string(), key);
+            opts.help.push_str(HISTOGRAM_SUM_HELP);
+
+            let new_vec = SnapshotHistogramVec::new(
+                opts,
+                &labels.keys().map(|s| s.as_str()).collect::<Vec<&str>>(),
+            )?;
+
+            set_labels(&new_vec, &mut self.histograms_lifetime)?;
+
+            // Register new metric
+            registry::register(self.subsystem, &new_vec)?;
+
+            let _ = self.histograms.insert(key.to_string(), new_vec);
+        }
+
+        Ok(true)
+    }
+
+    /// Insert Gauge type metric into collection, returns whether sample was
+    /// accepted
+    pub fn insert_fgauge(
+        &mut self,
+        key: &str,
+        value: f64,
+        labels: &Labels,
+        key_postfix: Option<&'static str>,
+        skippable: bool,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, prometheus::Error> {
+        let set_labels = |gauge: &GaugeVec,
+                          series: &mut HashMap<u64, Gauge>,
+                          lifetime: &mut lifetime::MetricLifetimeMap|
+         -> Result<(), prometheus::Error> {
+            with_series(
+                series,
+                lifetime,
+                key,
+                labels,
+                now,
+                |label_values| gauge.get_metric_with_label_values(label_values),
+                |gauge, _| gauge.set(value),
+            )
+        };
+
         if let Some(fgauge) = self.fgauges.get(key) {
-            set_labels(fgauge, &mut self.fgauges_lifetime)?;
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
+            }
+
+            set_labels(fgauge, &mut self.fgauges_series, &mut self.fgauges_lifetime)?;
         } else {
             // If metric is skippable and haven't been registered skip it
             // until value is not zero
             // is_normal: returns true if the number is neither zero, infinite, subnormal, or NaN.
             if skippable && self.options.exporter_skip_zero_metrics && !value.is_normal() {
-                return Ok(());
+                return Ok(false);
+            }
+
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
             }
 
             let mut metric_key = key.to_string();
@@ -101,25 +323,27 @@ impl Collection {
             }
 
             let new_fgauge = GaugeVec::new(
-                Opts::new(metric_key, key)
-                    .const_labels(self.const_labels.clone())
-                    .subsystem(self.subsystem)
-                    .namespace(self.options.exporter_metrics_namespace.clone()),
+                self.opts(metric_key, key),
                 &labels.keys().map(|s| s.as_str()).collect::<Vec<&str>>(),
             )?;
 
-            set_labels(&new_fgauge, &mut self.fgauges_lifetime)?;
+            set_labels(
+                &new_fgauge,
+                &mut self.fgauges_series,
+                &mut self.fgauges_lifetime,
+            )?;
 
             // Register new metric
-            default_registry().register(Box::new(new_fgauge.clone()))?;
+            registry::register(self.subsystem, &new_fgauge)?;
 
             let _ = self.fgauges.insert(key.to_string(), new_fgauge);
         }
 
-        Ok(())
+        Ok(true)
     }
 
-    /// Insert Gauge type metric into collection
+    /// Insert Gauge type metric into collection, returns whether sample was
+    /// accepted
     pub fn insert_gauge(
         &mut self,
         key: &str,
@@ -128,37 +352,39 @@ impl Collection {
         key_postfix: Option<&'static str>,
         skippable: bool,
         now: chrono::DateTime<chrono::Utc>,
-    ) -> Result<(), prometheus::Error> {
+    ) -> Result<bool, prometheus::Error> {
         let set_labels = |gauge: &IntGaugeVec,
+                          series: &mut HashMap<u64, IntGauge>,
                           lifetime: &mut lifetime::MetricLifetimeMap|
          -> Result<(), prometheus::Error> {
-            // BTreeMap ensures that values returned are always sorted
-            let label_values = &labels.values().map(|s| s.as_str()).collect::<Vec<&str>>();
-
-            gauge.get_metric_with_label_values(label_values)?.set(value);
-
-            if !label_values.is_empty() {
-                let _ = lifetime
-                    .entry(lifetime::hash_label(key, label_values))
-                    .or_insert_with(|| {
-                        lifetime::MetricLifetime::new(
-                            key.to_string(),
-                            labels.values().cloned().collect(),
-                        )
-                    })
-                    .reset_heartbeat(now);
-            }
-
-            Ok(())
+            with_series(
+                series,
+                lifetime,
+                key,
+                labels,
+                now,
+                |label_values| gauge.get_metric_with_label_values(label_values),
+                |gauge, _| gauge.set(value),
+            )
         };
 
         if let Some(gauge) = self.gauges.get(key) {
-            set_labels(gauge, &mut self.gauges_lifetime)?;
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
+            }
+
+            set_labels(gauge, &mut self.gauges_series, &mut self.gauges_lifetime)?;
         } else {
             // If metric is skippable and haven't been registered skip it
             // until value is not zero
             if skippable && self.options.exporter_skip_zero_metrics && value == 0 {
-                return Ok(());
+                return Ok(false);
+            }
+
+            if self.is_over_limit(key, labels) {
+                self.drop_series(key);
+                return Ok(false);
             }
 
             let mut metric_key = key.to_string();
@@ -168,22 +394,151 @@ impl Collection {
             }
 
             let new_gauge = IntGaugeVec::new(
-                Opts::new(metric_key, key)
-                    .const_labels(self.const_labels.clone())
-                    .subsystem(self.subsystem)
-                    .namespace(self.options.exporter_metrics_namespace.clone()),
+                self.opts(metric_key, key),
                 &labels.keys().map(|s| s.as_str()).collect::<Vec<&str>>(),
             )?;
 
-            set_labels(&new_gauge, &mut self.gauges_lifetime)?;
+            set_labels(
+                &new_gauge,
+                &mut self.gauges_series,
+                &mut self.gauges_lifetime,
+            )?;
 
             // Register new metric
-            default_registry().register(Box::new(new_gauge.clone()))?;
+            registry::register(self.subsystem, &new_gauge)?;
 
             let _ = self.gauges.insert(key.to_string(), new_gauge);
         }
 
-        Ok(())
+        Ok(true)
+    }
+
+    /// Metric options with HELP from embedded catalog falling back to metric key
+    fn opts(&self, metric_key: String, key: &str) -> Opts {
+        let description = catalog::describe(self.subsystem, key);
+
+        let opts = Opts::new(
+            metric_key,
+            description
+                .map(|description| description.help)
+                .unwrap_or(key),
+        )
+        .const_labels(self.const_labels.clone())
+        .subsystem(self.subsystem)
+        .namespace(self.options.exporter_metrics_namespace.clone());
+
+        catalog::register_unit(&opts.fq_name(), description);
+
+        opts
+    }
+
+    /// Emit sample to configured sinks, labels are merged with constant labels
+    fn sink(
+        &self,
+        key: &str,
+        postfix: Option<&str>,
+        labels: &Labels,
+        value: f64,
+        now: chrono::DateTime<chrono::Utc>,
+    ) {
+        if self.sinks.is_empty() {
+            return;
+        }
+
+        let mut key = key.to_string();
+
+        if let Some(postfix) = postfix {
+            key.push_str(postfix);
+        }
+
+        let mut labels = labels.clone();
+
+        for (name, value) in self.const_labels.iter() {
+            let _ = labels.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+
+        let sample = Sample {
+            subsystem: self.subsystem,
+            key: &key,
+            labels: &labels,
+            value,
+            timestamp: now.timestamp(),
+        };
+
+        for sink in self.sinks.iter() {
+            sink.push(&sample);
+        }
+    }
+
+    /// Labeled series of collection tracked by metric lifetime
+    pub fn series(&self) -> usize {
+        self.gauges_lifetime.len()
+            + self.fgauges_lifetime.len()
+            + self.counters_lifetime.len()
+            + self.histograms_lifetime.len()
+    }
+
+    /// Whether given labeled series is new and collection reached max series
+    fn is_over_limit(&self, key: &str, labels: &Labels) -> bool {
+        if self.max_series == 0 || labels.is_empty() || self.series() < self.max_series {
+            return false;
+        }
+
+        // Metric key belongs to a single metric type thus lifetime hash is
+        // unique across metric types
+        let hash = lifetime::hash_labels(key, labels);
+
+        ![
+            &self.gauges_lifetime,
+            &self.fgauges_lifetime,
+            &self.counters_lifetime,
+            &self.histograms_lifetime,
+        ]
+        .iter()
+        .any(|lifetime| lifetime.contains_key(&hash))
+    }
+
+    fn drop_series(&mut self, key: &str) {
+        match self.dropped.get_mut(key) {
+            Some(dropped) => *dropped += 1,
+            None => {
+                let _ = self.dropped.insert(key.to_string(), 1);
+            }
+        }
+    }
+
+    /// Take series dropped by max series limit by metric key since last taken
+    pub fn take_dropped(&mut self) -> HashMap<String, u64> {
+        std::mem::take(&mut self.dropped)
+    }
+
+    /// Unregister all metrics of collection and forget their series, same
+    /// metrics can be registered again by new collection of subsystem
+    pub fn unregister(&mut self) {
+        for gauge in self.gauges.values() {
+            registry::unregister(self.subsystem, gauge);
+        }
+        for gauge in self.fgauges.values() {
+            registry::unregister(self.subsystem, gauge);
+        }
+        for counter in self.counters.values() {
+            registry::unregister(self.subsystem, counter);
+        }
+        for histogram in self.histograms.values() {
+            registry::unregister(self.subsystem, histogram);
+        }
+
+        self.gauges.clear();
+        self.fgauges.clear();
+        self.counters.clear();
+        self.histograms.clear();
+        self.gauges_lifetime.clear();
+        self.fgauges_lifetime.clear();
+        self.counters_lifetime.clear();
+        self.histograms_lifetime.clear();
+        self.gauges_series.clear();
+        self.fgauges_series.clear();
+        self.counters_series.clear();
     }
 
     /// Return metric subsystem e.g.: cat_indices, cat_nodes, etc.
@@ -191,7 +546,9 @@ impl Collection {
         self.subsystem
     }
 
-    /// Collect given metrics
+    /// Collect given metrics, metric which fails to be inserted (e.g.: label
+    /// set of relabeled metric differs from registered one) is logged and
+    /// skipped, rest of metrics are still collected
     pub fn collect(&mut self, mut metrics: Vec<Metric>) -> Result<(), prometheus::Error> {
         let mut labels = Labels::new();
 
@@ -216,14 +573,20 @@ impl Collection {
         for metric in metrics.into_iter() {
             trace!("Collection metric: {:?}", metric);
 
-            let key = metric.key();
+            let Some((key, labels)) =
+                relabel::relabel(&self.relabel_configs, metric.key(), &labels)
+            else {
+                trace!("Collection relabel dropped metric: {:?}", metric);
+                continue;
+            };
+            let key = key.as_ref();
 
             match metric.metric_type() {
                 MetricType::Switch(value) => {
-                    if let Err(e) = self.insert_gauge(key, *value as i64, &labels, None, false, now)
-                    {
-                        error!("SWITCH insert_gauge {:?} err {}", metric, e);
-                        return Err(e);
+                    match self.insert_gauge(key, *value as i64, &labels, None, false, now) {
+                        Ok(true) => self.sink(key, None, &labels, f64::from(*value), now),
+                        Ok(false) => {}
+                        Err(e) => error!("SWITCH insert_gauge {:?} err {}", metric, e),
                     }
                 }
                 MetricType::Bytes(value) => {
@@ -233,21 +596,24 @@ impl Collection {
                     } else {
                         Some("_bytes")
                     };
-                    if let Err(e) = self.insert_gauge(key, *value, &labels, postfix, true, now) {
-                        error!("BYTES insert_gauge {:?} err {}", metric, e);
-                        return Err(e);
+                    match self.insert_gauge(key, *value, &labels, postfix, true, now) {
+                        Ok(true) => self.sink(key, postfix, &labels, *value as f64, now),
+                        Ok(false) => {}
+                        Err(e) => error!("BYTES insert_gauge {:?} err {}", metric, e),
                     }
                 }
                 MetricType::GaugeF(value) => {
-                    if let Err(e) = self.insert_fgauge(key, *value, &labels, None, true, now) {
-                        error!("GAUGEF insert_fgauge {:?} err {}", metric, e);
-                        return Err(e);
+                    match self.insert_fgauge(key, *value, &labels, None, true, now) {
+                        Ok(true) => self.sink(key, None, &labels, *value, now),
+                        Ok(false) => {}
+                        Err(e) => error!("GAUGEF insert_fgauge {:?} err {}", metric, e),
                     }
                 }
                 MetricType::Gauge(value) => {
-                    if let Err(e) = self.insert_gauge(key, *value, &labels, None, true, now) {
-                        error!("GAUGE insert_gauge {:?} err {}", metric, e);
-                        return Err(e);
+                    match self.insert_gauge(key, *value, &labels, None, true, now) {
+                        Ok(true) => self.sink(key, None, &labels, *value as f64, now),
+                        Ok(false) => {}
+                        Err(e) => error!("GAUGE insert_gauge {:?} err {}", metric, e),
                     }
                 }
                 MetricType::Time(duration) => {
@@ -257,11 +623,35 @@ impl Collection {
                         Some("_seconds")
                     };
 
-                    if let Err(e) =
-                        self.insert_fgauge(key, duration.as_secs_f64(), &labels, postfix, true, now)
-                    {
-                        error!("TIME insert_fgauge {:?} err {}", metric, e);
-                        return Err(e);
+                    match self.insert_fgauge(
+                        key,
+                        duration.as_secs_f64(),
+                        &labels,
+                        postfix,
+                        true,
+                        now,
+                    ) {
+                        Ok(true) => self.sink(key, postfix, &labels, duration.as_secs_f64(), now),
+                        Ok(false) => {}
+                        Err(e) => error!("TIME insert_fgauge {:?} err {}", metric, e),
+                    }
+                }
+                MetricType::Counter(value) => {
+                    let postfix = (!key.ends_with("_total")).then_some("_total");
+                    match self.insert_counter(key, *value, &labels, true, now) {
+                        Ok(true) => self.sink(key, postfix, &labels, *value as f64, now),
+                        Ok(false) => {}
+                        Err(e) => error!("COUNTER insert_counter {:?} err {}", metric, e),
+                    }
+                }
+                MetricType::Histogram(histogram) => {
+                    match self.insert_histogram(key, histogram, &labels, true, now) {
+                        Ok(true) => {
+                            self.sink(key, Some("_count"), &labels, histogram.count() as f64, now);
+                            self.sink(key, Some("_sum"), &labels, histogram.sum(), now);
+                        }
+                        Ok(false) => {}
+                        Err(e) => error!("HISTOGRAM insert_histogram {:?} err {}", metric, e),
                     }
                 }
                 _ => {}
@@ -272,8 +662,225 @@ impl Collection {
     }
 }
 
+/// Resolve series of labeled metric by lifetime hash and reset its lifetime
+/// heartbeat, series handle is looked up in metric vec only on first insert
+/// thus steady state polls do a single hash lookup per sample. `set` is told
+/// whether series handle was resolved from metric vec.
+fn with_series<T>(
+    series: &mut HashMap<u64, T>,
+    lifetime: &mut lifetime::MetricLifetimeMap,
+    key: &str,
+    labels: &Labels,
+    now: chrono::DateTime<chrono::Utc>,
+    resolve: impl FnOnce(&[&str]) -> Result<T, prometheus::Error>,
+    set: impl FnOnce(&T, bool),
+) -> Result<(), prometheus::Error> {
+    // Metrics without labels are never outdated thus not cached
+    if labels.is_empty() {
+        set(&resolve(&[])?, true);
+        return Ok(());
+    }
+
+    let hash = lifetime::hash_labels(key, labels);
+
+    match lifetime.get_mut(&hash) {
+        Some(entry) if entry.is_series(key, labels) => {
+            let _ = entry.reset_heartbeat(now);
+
+            if let Some(handle) = series.get(&hash) {
+                set(handle, false);
+                return Ok(());
+            }
+        }
+        Some(_) => {
+            // Label hash collides with another series, series is neither
+            // cached nor tracked by lifetime
+            let label_values = labels.values().map(|s| s.as_str()).collect::<Vec<&str>>();
+            set(&resolve(&label_values)?, true);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // BTreeMap ensures that values returned are always sorted
+    let label_values = labels.values().map(|s| s.as_str()).collect::<Vec<&str>>();
+    let handle = resolve(&label_values)?;
+    set(&handle, true);
+    let _ = series.insert(hash, handle);
+
+    let _ = lifetime
+        .entry(hash)
+        .or_insert_with(|| {
+            lifetime::MetricLifetime::new(key.to_string(), labels.values().cloned().collect())
+        })
+        .reset_heartbeat(now);
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_series_cache() {
+        let mut collection = Collection::new("test_collection", ExporterOptions::default());
+        let now = lifetime::now();
+
+        let mut labels = Labels::new();
+        let _ = labels.insert("index".into(), "logs".into());
+
+        let _ = collection
+            .insert_gauge("docs", 5, &labels, None, false, now)
+            .unwrap();
+        let _ = collection
+            .insert_gauge("docs", 7, &labels, None, false, now)
+            .unwrap();
+        assert_eq!(collection.gauges_series.len(), 1);
+        assert_eq!(collection.gauges_lifetime.len(), 1);
+        assert_eq!(
+            collection.gauges["docs"].with_label_values(&["logs"]).get(),
+            7
+        );
+
+        // Removed series is resolved again from metric vec
+        let gauge = &collection.gauges["docs"];
+        gauge.remove_label_values(&["logs"]).unwrap();
+        collection.gauges_series.clear();
+        collection.gauges_lifetime.clear();
+
+        let _ = collection
+            .insert_gauge("docs", 9, &labels, None, false, now)
+            .unwrap();
+        assert_eq!(
+            collection.gauges["docs"].with_label_values(&["logs"]).get(),
+            9
+        );
+    }
+
+    #[test]
+    fn test_collection_max_series() {
+        let mut collection = Collection::new("test_collection_limit", ExporterOptions::default());
+        collection.max_series = 1;
+        let now = lifetime::now();
+
+        let labels = |index: &str| Labels::from([("index".to_string(), index.to_string())]);
+
+        assert!(collection
+            .insert_gauge("docs", 1, &labels("a"), None, false, now)
+            .unwrap());
+        assert!(!collection
+            .insert_gauge("docs", 2, &labels("b"), None, false, now)
+            .unwrap());
+        assert!(!collection
+            .insert_counter("merges", 3, &labels("a"), false, now)
+            .unwrap());
+        // Known series is still updated
+        assert!(collection
+            .insert_gauge("docs", 4, &labels("a"), None, false, now)
+            .unwrap());
+
+        assert_eq!(collection.series(), 1);
+        assert_eq!(collection.gauges["docs"].with_label_values(&["a"]).get(), 4);
+        assert!(!collection.counters.contains_key("merges"));
+        assert_eq!(
+            collection.take_dropped(),
+            HashMap::from([("docs".to_string(), 1), ("merges".to_string(), 1)])
+        );
+        assert!(collection.take_dropped().is_empty());
+    }
+
+    #[test]
+    fn test_collection_sink_accepted() {
+        let sink = Arc::new(crate::sink::statsd::StatsdSink::new(
+            "127.0.0.1:8125".into(),
+            String::new(),
+            std::time::Duration::from_secs(10),
+        ));
+
+        let mut collection = Collection::new("test_collection_sink", ExporterOptions::default());
+        collection.include_labels = vec!["index".into()];
+        collection.sinks = vec![sink.clone()];
+        collection.max_series = 1;
+
+        for (index, merges) in [("a", 0), ("b", 1)] {
+            collection
+                .collect(vec![
+                    Metric("index".into(), MetricType::Label(index.into())),
+                    Metric("docs".into(), MetricType::Gauge(1)),
+                    Metric("merges".into(), MetricType::Gauge(merges)),
+                ])
+                .unwrap();
+        }
+
+        // Zero `merges` is skipped, series of index `b` are over max series
+        let lines = sink.take();
+        assert_eq!(lines.len(), 1, "{:?}", lines);
+        assert!(lines[0].starts_with("test_collection_sink.docs:1|g|#index:a"));
+    }
+
+    #[test]
+    fn test_collection_relabel_skips_failed_metric() {
+        let mut collection = Collection::new("test_collection_relabel", ExporterOptions::default());
+        collection.include_labels = vec!["index".into()];
+        // Fires only for some samples thus `docs` label set differs
+        collection.relabel_configs = serde_json::from_str(
+            r#"[{"source_labels": ["index"], "regex": "logs", "target_label": "tier", "replacement": "hot"}]"#,
+        )
+        .unwrap();
+
+        collection
+            .collect(vec![
+                Metric("index".into(), MetricType::Label("metrics".into())),
+                Metric("docs".into(), MetricType::Gauge(2)),
+            ])
+            .unwrap();
+        collection
+            .collect(vec![
+                Metric("index".into(), MetricType::Label("logs".into())),
+                Metric("docs".into(), MetricType::Gauge(1)),
+                Metric("merges".into(), MetricType::Gauge(3)),
+            ])
+            .unwrap();
+
+        assert_eq!(
+            collection.gauges["docs"]
+                .with_label_values(&["metrics"])
+                .get(),
+            2
+        );
+        // Inconsistent `docs` is skipped, rest of metric group is collected
+        assert_eq!(
+            collection.gauges["merges"]
+                .with_label_values(&["logs", "hot"])
+                .get(),
+            3
+        );
+        assert_eq!(collection.series(), 2);
+    }
+
+    #[test]
+    fn test_collection_unregister() {
+        let collect = || {
+            let mut collection = Collection::new("test_collection_unregister", Default::default());
+            collection.include_labels = vec!["index".into()];
+            collection
+                .collect(vec![
+                    Metric("index".into(), MetricType::Label("logs".into())),
+                    Metric("docs".into(), MetricType::Gauge(1)),
+                ])
+                .unwrap();
+            collection
+        };
+
+        let mut collection = collect();
+        assert_eq!(collection.series(), 1);
+        collection.unregister();
+        assert_eq!(collection.series(), 0);
+
+        // Same metrics are registered again by new collection
+        assert_eq!(collect().series(), 1);
+    }
 
     #[test]
     fn test_float_is_zero() {
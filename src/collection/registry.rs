@@ -0,0 +1,151 @@
+use prometheus::core::Collector;
+use prometheus::proto::MetricFamily;
+use prometheus::{default_registry, Registry};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, RwLock};
+
+/// Subsystem registries, metrics are registered in both subsystem and
+/// default registry so that each subsystem can be gathered separately
+static REGISTRIES: LazyLock<RwLock<BTreeMap<&'static str, Registry>>> =
+    LazyLock::new(Default::default);
+
+/// Subsystem by fully qualified name of metrics registered by subsystems,
+/// used to separate exporter own metrics from default registry
+static NAMES: LazyLock<RwLock<HashMap<String, &'static str>>> = LazyLock::new(Default::default);
+
+/// Exporter own metrics, e.g.: process, subsystem request duration
+pub const SELF: &str = "self";
+
+/// Subsystem registry, created on first use
+fn subsystem_registry(subsystem: &'static str) -> Registry {
+    REGISTRIES
+        .write()
+        .expect("registries lock")
+        .entry(subsystem)
+        .or_default()
+        .clone()
+}
+
+/// Initialize empty subsystem registry, subsystem is served before its
+/// metrics are registered
+pub(crate) fn init(subsystem: &'static str) {
+    let _ = subsystem_registry(subsystem);
+}
+
+/// Register metric in default and subsystem registry
+pub(crate) fn register<C>(subsystem: &'static str, collector: &C) -> Result<(), prometheus::Error>
+where
+    C: Collector + Clone + 'static,
+{
+    default_registry().register(Box::new(collector.clone()))?;
+
+    let registry = subsystem_registry(subsystem);
+
+    if let Err(e) = registry.register(Box::new(collector.clone())) {
+        let _ = default_registry().unregister(Box::new(collector.clone()));
+        return Err(e);
+    }
+
+    NAMES.write().expect("names lock").extend(
+        collector
+            .desc()
+            .into_iter()
+            .map(|desc| (desc.fq_name.clone(), subsystem)),
+    );
+
+    Ok(())
+}
+
+/// Unregister metric from default and subsystem registry
+pub(crate) fn unregister<C>(subsystem: &'static str, collector: &C)
+where
+    C: Collector + Clone + 'static,
+{
+    let _ = default_registry().unregister(Box::new(collector.clone()));
+    let _ = subsystem_registry(subsystem).unregister(Box::new(collector.clone()));
+}
+
+/// Subsystem of registered metric by fully qualified metric name
+pub fn subsystem(fq_name: &str) -> Option<&'static str> {
+    NAMES.read().expect("names lock").get(fq_name).copied()
+}
+
+/// Subsystems with registered metrics
+pub fn subsystems() -> Vec<&'static str> {
+    REGISTRIES
+        .read()
+        .expect("registries lock")
+        .keys()
+        .copied()
+        .collect()
+}
+
+/// Gather metric families of given subsystems or exporter own metrics
+/// (`self`), unknown subsystem is returned as error
+pub fn gather(subsystems: &[&str]) -> Result<Vec<MetricFamily>, String> {
+    let mut families = Vec::new();
+
+    for subsystem in subsystems.iter() {
+        if *subsystem == SELF {
+            families.extend(gather_self());
+            continue;
+        }
+
+        let registry = REGISTRIES
+            .read()
+            .expect("registries lock")
+            .get(subsystem)
+            .cloned()
+            .ok_or_else(|| subsystem.to_string())?;
+
+        families.extend(registry.gather());
+    }
+
+    families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+    families.dedup_by(|a, b| a.get_name() == b.get_name());
+
+    Ok(families)
+}
+
+/// Gather exporter own metrics, i.e. default registry metrics not
+/// registered by any subsystem
+fn gather_self() -> Vec<MetricFamily> {
+    let names = NAMES.read().expect("names lock");
+
+    prometheus::gather()
+        .into_iter()
+        .filter(|family| !names.contains_key(family.get_name()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{IntGaugeVec, Opts};
+
+    #[test]
+    fn test_registry_gather() {
+        let gauge = IntGaugeVec::new(
+            Opts::new("shards", "shards").subsystem("test_registry"),
+            &["cluster"],
+        )
+        .unwrap();
+        gauge.with_label_values(&["testing"]).set(5);
+
+        register("test_registry", &gauge).unwrap();
+        assert!(register("test_registry", &gauge).is_err());
+
+        let families = gather(&["test_registry"]).unwrap();
+        assert_eq!(families.len(), 1);
+        assert_eq!(families[0].get_name(), "test_registry_shards");
+
+        assert!(subsystems().contains(&"test_registry"));
+        assert_eq!(subsystem("test_registry_shards"), Some("test_registry"));
+        assert!(!gather(&[SELF])
+            .unwrap()
+            .iter()
+            .any(|family| family.get_name() == "test_registry_shards"));
+
+        assert_eq!(gather(&["unknown"]).unwrap_err(), "unknown");
+    }
+}
@@ -0,0 +1,333 @@
+use fnv::FnvHasher;
+use regex::Regex;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::Hasher;
+use std::sync::LazyLock;
+
+use crate::Labels;
+
+/// Reserved label holding metric key while relabeling
+pub const NAME_LABEL: &str = "__name__";
+
+/// Label name which may reference regex capture groups, e.g.: `es_${1}`
+static LABEL_NAME_TEMPLATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:[a-zA-Z_]|\$(?:\{\w+\}|\w+))(?:\w|\$(?:\{\w+\}|\w+))*$").expect("valid regex")
+});
+
+/// Metric name which may reference regex capture groups, e.g.: `${1}_${2}`
+static METRIC_NAME_TEMPLATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:[a-zA-Z_:]|\$(?:\{\w+\}|\w+))(?:[\w:]|\$(?:\{\w+\}|\w+))*$")
+        .expect("valid regex")
+});
+
+/// Validate label or metric name template, capture groups are expanded on
+/// relabel thus expanded names are validated once metric is registered
+fn validate_template(template: &Regex, kind: &str, name: &str) -> Result<(), String> {
+    if template.is_match(name) {
+        Ok(())
+    } else {
+        Err(format!("invalid {} `{}`", kind, name))
+    }
+}
+
+/// Relabel action, same as Prometheus `metric_relabel_configs` action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelabelAction {
+    /// Replace target label with regex replacement of concatenated source labels
+    #[default]
+    Replace,
+    /// Drop metric if concatenated source labels do not match regex
+    Keep,
+    /// Drop metric if concatenated source labels match regex
+    Drop,
+    /// Set target label to hash of concatenated source labels modulus
+    HashMod,
+    /// Copy labels which names match regex to replacement label name
+    LabelMap,
+    /// Remove labels which names match regex
+    LabelDrop,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRelabelConfig {
+    #[serde(default)]
+    action: RelabelAction,
+    #[serde(default)]
+    source_labels: Vec<String>,
+    #[serde(default)]
+    separator: Option<String>,
+    #[serde(default)]
+    regex: Option<String>,
+    #[serde(default)]
+    target_label: Option<String>,
+    #[serde(default)]
+    replacement: Option<String>,
+    #[serde(default)]
+    modulus: u64,
+}
+
+/// Prometheus style relabel config applied to metric key and labels before
+/// metric is registered, metric key is available as `__name__` label, e.g.:
+///
+/// {"source_labels": ["__name__"], "regex": "(.*):(.*):(.*)", "target_label": "__name__", "replacement": "${1}_${2}${3}"}
+///
+/// Regex is anchored on both ends, `source_labels` default to `["__name__"]`
+/// and `hashmod` uses FNV hash, thus shards differ from Prometheus ones
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawRelabelConfig")]
+pub struct RelabelConfig {
+    action: RelabelAction,
+    source_labels: Vec<String>,
+    separator: String,
+    regex: Regex,
+    target_label: String,
+    replacement: String,
+    modulus: u64,
+    source: String,
+}
+
+impl TryFrom<RawRelabelConfig> for RelabelConfig {
+    type Error = String;
+
+    fn try_from(raw: RawRelabelConfig) -> Result<Self, Self::Error> {
+        let regex = raw.regex.as_deref().unwrap_or("(.*)");
+        let target_label = raw.target_label.unwrap_or_default();
+
+        match raw.action {
+            RelabelAction::Replace | RelabelAction::HashMod if target_label.is_empty() => {
+                return Err(format!("{:?} requires `target_label`", raw.action));
+            }
+            RelabelAction::HashMod if raw.modulus == 0 => {
+                return Err("HashMod requires non zero `modulus`".into());
+            }
+            _ => {}
+        }
+
+        let replacement = raw.replacement.unwrap_or_else(|| "$1".into());
+
+        match raw.action {
+            RelabelAction::Replace if target_label == NAME_LABEL => {
+                validate_template(
+                    &METRIC_NAME_TEMPLATE,
+                    "metric name replacement",
+                    &replacement,
+                )?;
+            }
+            RelabelAction::Replace | RelabelAction::HashMod => {
+                validate_template(&LABEL_NAME_TEMPLATE, "target_label", &target_label)?;
+            }
+            RelabelAction::LabelMap => {
+                validate_template(&LABEL_NAME_TEMPLATE, "label name replacement", &replacement)?;
+            }
+            _ => {}
+        }
+
+        let source_labels = if raw.source_labels.is_empty() {
+            vec![NAME_LABEL.to_string()]
+        } else {
+            raw.source_labels
+        };
+
+        let source = format!(
+            "{:?} [{}] `{}` -> {}",
+            raw.action,
+            source_labels.join(","),
+            regex,
+            target_label
+        );
+
+        Ok(Self {
+            action: raw.action,
+            source_labels,
+            separator: raw.separator.unwrap_or_else(|| ";".into()),
+            regex: Regex::new(&format!("^(?:{})$", regex))
+                .map_err(|e| format!("invalid regex `{}` {}", regex, e))?,
+            target_label,
+            replacement,
+            modulus: raw.modulus,
+            source,
+        })
+    }
+}
+
+impl fmt::Display for RelabelConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl RelabelConfig {
+    /// Relabel action
+    pub fn action(&self) -> RelabelAction {
+        self.action
+    }
+
+    /// Apply relabel config to labels, returns false if metric must be dropped
+    fn apply(&self, labels: &mut Labels) -> bool {
+        let value = self
+            .source_labels
+            .iter()
+            .map(|name| labels.get(name).map(String::as_str).unwrap_or_default())
+            .collect::<Vec<&str>>()
+            .join(&self.separator);
+
+        match self.action {
+            RelabelAction::Keep => return self.regex.is_match(&value),
+            RelabelAction::Drop => return !self.regex.is_match(&value),
+            RelabelAction::Replace => {
+                let Some(captures) = self.regex.captures(&value) else {
+                    return true;
+                };
+
+                let mut target = String::new();
+                captures.expand(&self.target_label, &mut target);
+
+                let mut replacement = String::new();
+                captures.expand(&self.replacement, &mut replacement);
+
+                if replacement.is_empty() {
+                    let _ = labels.remove(&target);
+                } else {
+                    let _ = labels.insert(target, replacement);
+                }
+            }
+            RelabelAction::HashMod => {
+                let mut h = FnvHasher::default();
+                h.write(value.as_bytes());
+
+                let _ = labels.insert(
+                    self.target_label.clone(),
+                    (h.finish() % self.modulus).to_string(),
+                );
+            }
+            RelabelAction::LabelMap => {
+                let mut mapped = Labels::new();
+
+                for (name, value) in labels.iter() {
+                    if let Some(captures) = self.regex.captures(name) {
+                        let mut target = String::new();
+                        captures.expand(&self.replacement, &mut target);
+
+                        let _ = mapped.insert(target, value.clone());
+                    }
+                }
+
+                labels.extend(mapped);
+            }
+            RelabelAction::LabelDrop => {
+                labels.retain(|name, _| name == NAME_LABEL || !self.regex.is_match(name));
+            }
+        }
+
+        true
+    }
+}
+
+/// Apply relabel configs in order to metric key and labels, returns `None`
+/// in case metric was dropped. Key and labels are borrowed if there is
+/// nothing to relabel
+pub fn relabel<'a>(
+    configs: &[RelabelConfig],
+    key: &'a str,
+    labels: &'a Labels,
+) -> Option<(Cow<'a, str>, Cow<'a, Labels>)> {
+    if configs.is_empty() {
+        return Some((Cow::Borrowed(key), Cow::Borrowed(labels)));
+    }
+
+    let mut relabeled = labels.clone();
+    let _ = relabeled.insert(NAME_LABEL.into(), key.to_string());
+
+    for config in configs.iter() {
+        if !config.apply(&mut relabeled) {
+            return None;
+        }
+    }
+
+    let key = relabeled.remove(NAME_LABEL).filter(|key| !key.is_empty())?;
+
+    Some((Cow::Owned(key), Cow::Owned(relabeled)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configs(input: &str) -> Vec<RelabelConfig> {
+        serde_json::from_str(input).expect("valid relabel configs")
+    }
+
+    #[test]
+    fn test_relabel() {
+        let configs = configs(
+            r#"[
+                {"regex": "(.*):(.*):(.*)", "target_label": "__name__", "replacement": "${1}_${2}${3}"},
+                {"action": "drop", "regex": "transport_actions_internal_.*"},
+                {"action": "labelmap", "regex": "node_(.*)", "replacement": "es_${1}"},
+                {"action": "labeldrop", "regex": "node_.*"},
+                {"action": "hashmod", "source_labels": ["es_name"], "modulus": 4, "target_label": "shard"},
+                {"source_labels": ["es_name", "index"], "regex": "(.*);(.*)", "target_label": "target", "replacement": "$2@$1"}
+            ]"#,
+        );
+
+        let mut labels = Labels::new();
+        let _ = labels.insert("node_name".into(), "m1".into());
+        let _ = labels.insert("index".into(), "logs".into());
+
+        let (key, relabeled) = relabel(
+            &configs,
+            "transport_actions_cluster_monitor_nodes_info:n:_requests_count",
+            &labels,
+        )
+        .unwrap();
+
+        assert_eq!(
+            key,
+            "transport_actions_cluster_monitor_nodes_info_n_requests_count"
+        );
+        assert!(relabeled.get("node_name").is_none());
+        assert_eq!(relabeled.get("es_name").unwrap(), "m1");
+        assert_eq!(relabeled.get("target").unwrap(), "logs@m1");
+        assert!(relabeled.get("shard").unwrap().parse::<u64>().unwrap() < 4);
+        assert!(relabeled.get(NAME_LABEL).is_none());
+
+        assert!(relabel(&configs, "transport_actions_internal_ping", &labels).is_none());
+
+        let keep = self::configs(r#"[{"action": "keep", "regex": "indices_.*"}]"#);
+        assert!(relabel(&keep, "indices_docs_count", &labels).is_some());
+        assert!(relabel(&keep, "jvm_uptime", &labels).is_none());
+
+        // Nothing to relabel
+        let (key, relabeled) = relabel(&[], "jvm_uptime", &labels).unwrap();
+        assert!(matches!(key, Cow::Borrowed(_)));
+        assert!(matches!(relabeled, Cow::Borrowed(_)));
+
+        assert!(serde_json::from_str::<Vec<RelabelConfig>>(r#"[{"regex": "("}]"#).is_err());
+        assert!(serde_json::from_str::<Vec<RelabelConfig>>(
+            r#"[{"action": "hashmod", "target_label": "shard"}]"#
+        )
+        .is_err());
+
+        // Invalid label and metric names are rejected on load
+        for invalid in [
+            r#"[{"target_label": "node-name"}]"#,
+            r#"[{"target_label": "1st"}]"#,
+            r#"[{"target_label": "__name__", "replacement": "jvm.uptime"}]"#,
+            r#"[{"action": "labelmap", "regex": "node_(.*)", "replacement": "es.${1}"}]"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Vec<RelabelConfig>>(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+        assert!(serde_json::from_str::<Vec<RelabelConfig>>(
+            r#"[{"source_labels": ["name"], "target_label": "node_${1}", "replacement": "any value"}]"#
+        )
+        .is_ok());
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{de, Deserialize, Deserializer};
+use std::convert::TryFrom;
+
+/// Subsystem name of user configured subsystem, e.g.: `query_service_docs`,
+/// name must consist of [a-z0-9_] characters
+///
+/// User subsystems are configured once on start, subsystem name is leaked as
+/// it lives as long as the exporter does
+pub(crate) fn subsystem_name(prefix: &str, kind: &str, name: &str) -> Result<&'static str, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(format!(
+            "{} name `{}` must consist of [a-z0-9_] characters",
+            kind, name
+        ));
+    }
+
+    Ok(Box::leak(format!("{}{}", prefix, name).into_boxed_str()))
+}
+
+/// Deserialize config validated by `TryFrom` of its raw config
+///
+/// Derived `#[serde(try_from = "...")]` of config holding leaked `&'static str`
+/// subsystem name only implements `Deserialize<'static>`, thus such configs
+/// implement `Deserialize` with this helper
+pub(crate) fn deserialize_try_from<'de, D, R, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    R: Deserialize<'de>,
+    T: TryFrom<R, Error = String>,
+{
+    T::try_from(R::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+#[test]
+fn test_subsystem_name() {
+    assert_eq!(
+        subsystem_name("query_", "query", "service_docs"),
+        Ok("query_service_docs")
+    );
+    assert!(subsystem_name("query_", "query", "Bad-Name").is_err());
+    assert!(subsystem_name("endpoint_", "endpoint", "").is_err());
+}
@@ -0,0 +1,347 @@
+use elasticsearch::http::Method;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::config;
+use crate::metric::{self, MetricRule};
+
+/// Subsystem name prefix of REST endpoints, e.g.: endpoint_ccr_stats
+pub const SUBSYSTEM_PREFIX: &str = "endpoint_";
+
+/// HTTP method of REST endpoint
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum EndpointMethod {
+    #[default]
+    Get,
+    Post,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEndpointConfig {
+    name: String,
+    #[serde(default)]
+    method: EndpointMethod,
+    path: String,
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+    #[serde(default)]
+    filter_path: Option<String>,
+    #[serde(default)]
+    body: Option<Value>,
+    #[serde(default)]
+    list: Option<String>,
+    #[serde(default)]
+    key_label: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+/// User defined REST endpoint polled as subsystem `endpoint_<name>`, e.g.:
+///
+/// {"name": "ccr_stats", "path": "/_ccr/stats", "filter_path": "follow_stats.indices",
+///  "list": "follow_stats.indices", "labels": ["index"], "remove": ["shards"]}
+///
+/// `list` is dotted path to list of objects in response, `*` selects all
+/// values of an object (e.g.: `nodes.*`) and object key becomes `key_label`
+/// label, each selected object is flattened as a metric value of its own
+/// with `labels` fields as labels and `remove` keys removed
+#[derive(Debug, Clone)]
+pub struct EndpointConfig {
+    subsystem: &'static str,
+    method: Method,
+    path: String,
+    params: BTreeMap<String, String>,
+    body: Option<Value>,
+    list: Vec<String>,
+    key_label: Option<String>,
+    labels: Vec<String>,
+    remove: Vec<String>,
+    rules: Vec<MetricRule>,
+}
+
+impl TryFrom<RawEndpointConfig> for EndpointConfig {
+    type Error = String;
+
+    fn try_from(raw: RawEndpointConfig) -> Result<Self, Self::Error> {
+        let subsystem = config::subsystem_name(SUBSYSTEM_PREFIX, "endpoint", &raw.name)?;
+
+        if !raw.path.starts_with('/') {
+            return Err(format!(
+                "endpoint `{}` path `{}` must start with `/`",
+                raw.name, raw.path
+            ));
+        }
+
+        let mut params = raw.params;
+
+        if let Some(filter_path) = raw.filter_path {
+            let _ = params.insert("filter_path".into(), filter_path);
+        }
+
+        // Label fields as flattened metric keys, e.g.: `shard.id` -> `shard_id`
+        let labels: Vec<String> = raw
+            .labels
+            .iter()
+            .chain(raw.key_label.iter())
+            .map(|label| metric::field_key(label))
+            .collect();
+
+        let rules = labels
+            .iter()
+            .map(|label| format!("{}:label", label).parse::<MetricRule>())
+            .collect::<Result<Vec<MetricRule>, _>>()
+            .map_err(|e| format!("endpoint `{}` label rule err {}", raw.name, e))?;
+
+        Ok(Self {
+            subsystem,
+            method: match raw.method {
+                EndpointMethod::Get => Method::Get,
+                EndpointMethod::Post => Method::Post,
+            },
+            path: raw.path,
+            params,
+            body: raw.body,
+            list: raw
+                .list
+                .iter()
+                .flat_map(|list| list.split('.'))
+                .filter(|segment| !segment.is_empty())
+                .map(String::from)
+                .collect(),
+            key_label: raw.key_label,
+            labels,
+            remove: raw.remove,
+            rules,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for EndpointConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        config::deserialize_try_from::<D, RawEndpointConfig, Self>(deserializer)
+    }
+}
+
+impl EndpointConfig {
+    /// Subsystem name, e.g.: endpoint_ccr_stats
+    pub fn subsystem(&self) -> &'static str {
+        self.subsystem
+    }
+
+    /// HTTP method
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Request path, e.g.: /_ccr/stats
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Request query parameters including `filter_path`
+    pub fn params(&self) -> &BTreeMap<String, String> {
+        &self.params
+    }
+
+    /// Request body
+    pub fn body(&self) -> Option<&Value> {
+        self.body.as_ref()
+    }
+
+    /// Flattened metric keys of fields included as labels, including
+    /// `key_label`, e.g.: `shard_id` of `shard.id` field
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Metric type rules keeping label fields as labels, e.g.: numeric ids
+    pub fn rules(&self) -> &[MetricRule] {
+        &self.rules
+    }
+
+    /// Metric values of response objects selected by `list`
+    pub fn values(&self, response: Value) -> Vec<Value> {
+        let mut values = Vec::new();
+
+        select(response, &self.list, None, &mut |key, value| {
+            let Value::Object(mut object) = value else {
+                return;
+            };
+
+            for remove in self.remove.iter() {
+                remove_path(&mut object, remove);
+            }
+
+            if let (Some(label), Some(key)) = (self.key_label.as_ref(), key) {
+                let _ = object.insert(label.clone(), Value::String(key));
+            }
+
+            values.push(Value::Object(object));
+        });
+
+        values
+    }
+}
+
+/// Select values at dotted path, array at the end of path is expanded into
+/// its elements, `*` selects all values of object along with their keys
+fn select(
+    value: Value,
+    path: &[String],
+    key: Option<String>,
+    output: &mut impl FnMut(Option<String>, Value),
+) {
+    let Some((segment, rest)) = path.split_first() else {
+        match value {
+            Value::Array(values) => values
+                .into_iter()
+                .for_each(|value| output(key.clone(), value)),
+            value => output(key, value),
+        }
+        return;
+    };
+
+    match (segment.as_str(), value) {
+        ("*", Value::Object(object)) => {
+            for (key, value) in object.into_iter() {
+                select(value, rest, Some(key), output);
+            }
+        }
+        ("*", Value::Array(values)) => {
+            for value in values.into_iter() {
+                select(value, rest, key.clone(), output);
+            }
+        }
+        (segment, Value::Object(mut object)) => {
+            if let Some(value) = object.remove(segment) {
+                select(value, rest, key, output);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove key at dotted path of object
+fn remove_path(object: &mut serde_json::Map<String, Value>, path: &str) {
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            if let Some(Value::Object(object)) = object.get_mut(head) {
+                remove_path(object, rest);
+            }
+        }
+        None => {
+            let _ = object.remove(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_endpoint_config() {
+        let config: EndpointConfig = serde_json::from_str(
+            r#"{
+                "name": "ccr_stats",
+                "path": "/_ccr/stats",
+                "params": {"timeout": "10s"},
+                "filter_path": "follow_stats.indices",
+                "list": "follow_stats.indices",
+                "labels": ["index"]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.subsystem(), "endpoint_ccr_stats");
+        assert_eq!(config.method(), Method::Get);
+        assert_eq!(config.params()["filter_path"], "follow_stats.indices");
+        assert_eq!(config.labels(), &["index"]);
+
+        assert!(
+            serde_json::from_str::<EndpointConfig>(r#"{"name": "ccr", "path": "_ccr/stats"}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_endpoint_values() {
+        let config: EndpointConfig = serde_json::from_str(
+            r#"{
+                "name": "nodes_stats",
+                "path": "/_nodes/stats/indexing_pressure",
+                "list": "nodes.*",
+                "key_label": "node_id",
+                "labels": ["name"],
+                "remove": ["roles", "indexing_pressure.memory.limit"]
+            }"#,
+        )
+        .unwrap();
+
+        let values = config.values(json!({
+            "_nodes": {"total": 2},
+            "nodes": {
+                "abc": {
+                    "name": "node-1",
+                    "roles": ["data"],
+                    "indexing_pressure": {"memory": {"limit": "10mb", "current": 5}}
+                },
+                "def": {"name": "node-2", "roles": ["master"]}
+            }
+        }));
+
+        assert_eq!(
+            values,
+            vec![
+                json!({"node_id": "abc", "name": "node-1", "indexing_pressure": {"memory": {"current": 5}}}),
+                json!({"node_id": "def", "name": "node-2"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_endpoint_dotted_labels() {
+        use crate::collection::Collection;
+
+        let config: EndpointConfig = serde_json::from_str(
+            r#"{
+                "name": "shard_stores",
+                "path": "/_shard_stores",
+                "list": "indices.*",
+                "key_label": "index.name",
+                "labels": ["shard.id"]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.labels(), &["shard_id", "index_name"]);
+
+        let values = config.values(json!({
+            "indices": {"logs": {"shard.id": 3, "store_size": 100}}
+        }));
+
+        let mut collection = Collection::new("test_endpoint_shard_stores", Default::default());
+        collection.include_labels = config.labels().to_vec();
+
+        for metrics in metric::from_values_with_rules(values, config.rules()) {
+            collection.collect(metrics).unwrap();
+        }
+
+        assert_eq!(
+            collection.gauges["store_size"]
+                .with_label_values(&["logs", "3"])
+                .get(),
+            100
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use flate2::{write::GzEncoder, Compression};
+use prometheus::proto::{self, MetricFamily};
+use prometheus::{Encoder, ProtobufEncoder, TextEncoder};
+use std::io::Write;
+
+use crate::collection::created;
+use crate::metric::catalog;
+
+/// OpenMetrics text format content type
+pub const OPENMETRICS_FORMAT: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Exposition format negotiated from `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Classic Prometheus text format
+    Text,
+    /// OpenMetrics text format
+    OpenMetrics,
+    /// Prometheus protobuf delimited format
+    Protobuf,
+}
+
+impl Format {
+    /// Negotiate format from `Accept` header, media ranges are ordered by
+    /// quality, classic text format is used if nothing else is acceptable
+    pub fn negotiate(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Format::Text;
+        };
+
+        let mut best: Option<(f32, Self)> = None;
+
+        for range in accept.split(',') {
+            let mut params = range.split(';').map(str::trim);
+            let media_type = params.next().unwrap_or_default().to_ascii_lowercase();
+
+            let mut quality: f32 = 1.0;
+            let mut proto: Option<&str> = None;
+            let mut encoding: Option<&str> = None;
+
+            for param in params {
+                match param.split_once('=') {
+                    Some(("q", value)) => quality = value.parse().unwrap_or(0.0),
+                    Some(("proto", value)) => proto = Some(value),
+                    Some(("encoding", value)) => encoding = Some(value),
+                    _ => {}
+                }
+            }
+
+            let format = match media_type.as_str() {
+                "application/openmetrics-text" => Format::OpenMetrics,
+                "application/vnd.google.protobuf"
+                    if proto == Some("io.prometheus.client.MetricFamily")
+                        && encoding == Some("delimited") =>
+                {
+                    Format::Protobuf
+                }
+                "text/plain" | "text/*" | "*/*" => Format::Text,
+                _ => continue,
+            };
+
+            // Equal quality keeps the first media range
+            if quality > 0.0 && best.is_none_or(|(best, _)| quality > best) {
+                best = Some((quality, format));
+            }
+        }
+
+        best.map(|(_, format)| format).unwrap_or(Format::Text)
+    }
+
+    /// Response content type
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Text => prometheus::TEXT_FORMAT,
+            Format::OpenMetrics => OPENMETRICS_FORMAT,
+            Format::Protobuf => prometheus::PROTOBUF_FORMAT,
+        }
+    }
+
+    /// Encode metric families into buffer
+    pub fn encode(
+        &self,
+        families: &[MetricFamily],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), prometheus::Error> {
+        match self {
+            Format::Text => TextEncoder::new().encode(families, buffer),
+            Format::OpenMetrics => OpenMetricsEncoder.encode(families, buffer),
+            Format::Protobuf => ProtobufEncoder::new().encode(families, buffer),
+        }
+    }
+}
+
+/// Whether `Accept-Encoding` header allows gzip
+pub fn accepts_gzip(accept_encoding: Option<&str>) -> bool {
+    accept_encoding.is_some_and(|accept_encoding| {
+        accept_encoding.split(',').any(|coding| {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+
+            (name.eq_ignore_ascii_case("gzip") || name == "*")
+                && !params.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q == 0.0)
+                })
+        })
+    })
+}
+
+/// Gzip encoded buffer
+pub fn gzip(buffer: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(buffer.len() / 8), Compression::fast());
+    encoder.write_all(buffer)?;
+    encoder.finish()
+}
+
+/// OpenMetrics text format encoder
+///
+/// https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md
+#[derive(Debug, Default)]
+pub struct OpenMetricsEncoder;
+
+impl Encoder for OpenMetricsEncoder {
+    fn encode<W: Write>(
+        &self,
+        families: &[MetricFamily],
+        writer: &mut W,
+    ) -> prometheus::Result<()> {
+        for family in families.iter() {
+            if family.get_metric().is_empty() {
+                continue;
+            }
+
+            let fq_name = family.get_name();
+            let field_type = family.get_field_type();
+
+            // Counter family name does not include `_total` postfix
+            let name = match field_type {
+                proto::MetricType::COUNTER => fq_name.strip_suffix("_total").unwrap_or(fq_name),
+                _ => fq_name,
+            };
+
+            let type_name = match field_type {
+                proto::MetricType::COUNTER => "counter",
+                proto::MetricType::GAUGE => "gauge",
+                proto::MetricType::HISTOGRAM => "histogram",
+                proto::MetricType::SUMMARY => "summary",
+                proto::MetricType::UNTYPED => "unknown",
+            };
+
+            writeln!(writer, "# TYPE {} {}", name, type_name)?;
+
+            if let Some(unit) = catalog::unit(fq_name) {
+                writeln!(writer, "# UNIT {} {}", name, unit)?;
+            }
+
+            if !family.get_help().is_empty() {
+                writeln!(writer, "# HELP {} {}", name, escape(family.get_help()))?;
+            }
+
+            for metric in family.get_metric().iter() {
+                let labels = metric.get_label();
+                let timestamp = metric.get_timestamp_ms();
+
+                let created = || {
+                    created::get(
+                        fq_name,
+                        &labels.iter().map(|l| l.get_value()).collect::<Vec<&str>>(),
+                    )
+                };
+
+                match field_type {
+                    proto::MetricType::COUNTER => {
+                        let value = metric.get_counter().get_value();
+                        write_sample(writer, name, "_total", labels, None, value, timestamp)?;
+
+                        if let Some(created) = created() {
+                            write_sample(writer, name, "_created", labels, None, created, 0)?;
+                        }
+                    }
+                    proto::MetricType::GAUGE => {
+                        let value = metric.get_gauge().get_value();
+                        write_sample(writer, name, "", labels, None, value, timestamp)?;
+                    }
+                    proto::MetricType::UNTYPED => {
+                        let value = metric.get_untyped().get_value();
+                        write_sample(writer, name, "", labels, None, value, timestamp)?;
+                    }
+                    proto::MetricType::HISTOGRAM => {
+                        let histogram = metric.get_histogram();
+
+                        let mut inf_seen = false;
+                        for bucket in histogram.get_bucket().iter() {
+                            let upper_bound = bucket.get_upper_bound();
+                            inf_seen |= upper_bound == f64::INFINITY;
+
+                            write_sample(
+                                writer,
+                                name,
+                                "_bucket",
+                                labels,
+                                Some(("le", upper_bound)),
+                                bucket.get_cumulative_count() as f64,
+                                timestamp,
+                            )?;
+                        }
+
+                        if !inf_seen {
+                            write_sample(
+                                writer,
+                                name,
+                                "_bucket",
+                                labels,
+                                Some(("le", f64::INFINITY)),
+                                histogram.get_sample_count() as f64,
+                                timestamp,
+                            )?;
+                        }
+
+                        write_sample(
+                            writer,
+                            name,
+                            "_count",
+                            labels,
+                            None,
+                            histogram.get_sample_count() as f64,
+                            timestamp,
+                        )?;
+                        write_sample(
+                            writer,
+                            name,
+                            "_sum",
+                            labels,
+                            None,
+                            histogram.get_sample_sum(),
+                            timestamp,
+                        )?;
+
+                        if let Some(created) = created() {
+                            write_sample(writer, name, "_created", labels, None, created, 0)?;
+                        }
+                    }
+                    proto::MetricType::SUMMARY => {
+                        let summary = metric.get_summary();
+
+                        for quantile in summary.get_quantile().iter() {
+                            write_sample(
+                                writer,
+                                name,
+                                "",
+                                labels,
+                                Some(("quantile", quantile.get_quantile())),
+                                quantile.get_value(),
+                                timestamp,
+                            )?;
+                        }
+
+                        write_sample(
+                            writer,
+                            name,
+                            "_count",
+                            labels,
+                            None,
+                            summary.get_sample_count() as f64,
+                            timestamp,
+                        )?;
+                        write_sample(
+                            writer,
+                            name,
+                            "_sum",
+                            labels,
+                            None,
+                            summary.get_sample_sum(),
+                            timestamp,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        writeln!(writer, "# EOF")?;
+
+        Ok(())
+    }
+
+    fn format_type(&self) -> &str {
+        OPENMETRICS_FORMAT
+    }
+}
+
+fn write_sample<W: Write>(
+    writer: &mut W,
+    name: &str,
+    postfix: &str,
+    labels: &[proto::LabelPair],
+    extra_label: Option<(&str, f64)>,
+    value: f64,
+    timestamp_ms: i64,
+) -> prometheus::Result<()> {
+    write!(writer, "{}{}", name, postfix)?;
+
+    if !labels.is_empty() || extra_label.is_some() {
+        let mut separator = '{';
+
+        for label in labels.iter() {
+            write!(
+                writer,
+                "{}{}=\"{}\"",
+                separator,
+                label.get_name(),
+                escape(label.get_value())
+            )?;
+            separator = ',';
+        }
+
+        if let Some((name, value)) = extra_label {
+            write!(writer, "{}{}=\"{}\"", separator, name, format_float(value))?;
+        }
+
+        write!(writer, "}}")?;
+    }
+
+    write!(writer, " {}", format_float(value))?;
+
+    if timestamp_ms != 0 {
+        write!(writer, " {}", format_float(timestamp_ms as f64 / 1000.0))?;
+    }
+
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+fn format_float(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".into()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".into()
+    } else if value.is_nan() {
+        "NaN".into()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::histogram::SnapshotHistogramVec;
+    use crate::metric::Histogram;
+    use prometheus::{IntCounterVec, Opts, Registry};
+    use serde_json::Value;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Format::negotiate(None), Format::Text);
+        assert_eq!(Format::negotiate(Some("*/*")), Format::Text);
+        assert_eq!(
+            Format::negotiate(Some(
+                "application/openmetrics-text;version=1.0.0,application/openmetrics-text;version=0.0.1;q=0.75,text/plain;version=0.0.4;q=0.5,*/*;q=0.1"
+            )),
+            Format::OpenMetrics
+        );
+        assert_eq!(
+            Format::negotiate(Some(
+                "application/vnd.google.protobuf;proto=io.prometheus.client.MetricFamily;encoding=delimited;q=0.7,text/plain;version=0.0.4;q=0.3"
+            )),
+            Format::Protobuf
+        );
+        assert_eq!(
+            Format::negotiate(Some("text/plain;q=0.9,application/openmetrics-text;q=0.5")),
+            Format::Text
+        );
+        assert_eq!(Format::negotiate(Some("application/json")), Format::Text);
+
+        assert!(accepts_gzip(Some("gzip, deflate")));
+        assert!(accepts_gzip(Some("br;q=1.0, gzip;q=0.8")));
+        assert!(!accepts_gzip(Some("gzip;q=0")));
+        assert!(!accepts_gzip(Some("identity")));
+        assert!(!accepts_gzip(None));
+    }
+
+    #[test]
+    fn test_openmetrics_encode() {
+        let registry = Registry::new();
+
+        let counter = IntCounterVec::new(
+            Opts::new("test_requests_total", "Number of \"requests\"").namespace("elasticsearch"),
+            &["name"],
+        )
+        .unwrap();
+        counter.with_label_values(&["node"]).inc_by(3);
+        created::set(
+            &counter,
+            &["node"],
+            chrono::DateTime::from_timestamp(1700000000, 0).unwrap(),
+            false,
+        );
+        registry.register(Box::new(counter.clone())).unwrap();
+
+        let buckets: Value =
+            serde_json::from_str(r#"[{"lt_millis": 1, "count": 2}, {"ge_millis": 1, "count": 1}]"#)
+                .unwrap();
+        let histogram = SnapshotHistogramVec::new(
+            Opts::new("test_handling_time_seconds", "handling_time").namespace("elasticsearch"),
+            &["name"],
+        )
+        .unwrap();
+        histogram
+            .set(
+                &["node"],
+                Histogram::from_buckets(buckets.as_array().unwrap()).unwrap(),
+            )
+            .unwrap();
+        catalog::register_unit("elasticsearch_test_handling_time_seconds", None);
+        registry.register(Box::new(histogram)).unwrap();
+
+        let mut buffer = vec![];
+        Format::OpenMetrics
+            .encode(&registry.gather(), &mut buffer)
+            .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        assert!(output.contains("# TYPE elasticsearch_test_requests counter\n"));
+        assert!(output.contains("# HELP elasticsearch_test_requests Number of \\\"requests\\\"\n"));
+        assert!(output.contains("elasticsearch_test_requests_total{name=\"node\"} 3\n"));
+        assert!(output.contains("elasticsearch_test_requests_created{name=\"node\"} 1700000000\n"));
+
+        assert!(output.contains("# TYPE elasticsearch_test_handling_time_seconds histogram\n"));
+        assert!(output.contains("# UNIT elasticsearch_test_handling_time_seconds seconds\n"));
+        assert!(output.contains(
+            "elasticsearch_test_handling_time_seconds_bucket{name=\"node\",le=\"+Inf\"} 3\n"
+        ));
+        assert!(
+            output.contains("elasticsearch_test_handling_time_seconds_count{name=\"node\"} 3\n")
+        );
+        assert!(output.ends_with("# EOF\n"));
+
+        let compressed = gzip(output.as_bytes()).unwrap();
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+    }
+}
@@ -0,0 +1,232 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::TryFrom;
+use std::time::Duration;
+
+/// Subsystem name of index freshness metrics
+pub const SUBSYSTEM: &str = "freshness";
+
+/// Default timestamp field of searched documents
+const DEFAULT_FIELD: &str = "@timestamp";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFreshnessConfig {
+    name: String,
+    index: String,
+    #[serde(default)]
+    field: Option<String>,
+    #[serde(default)]
+    timeout: Option<String>,
+}
+
+/// Index pattern or data stream group polled for its latest document, e.g.:
+///
+/// {"name": "nginx", "index": "logs-nginx-*,logs-ingress-*", "field": "event.ingested", "timeout": "5s"}
+///
+/// Latest document is searched with `max` aggregation of `field`, defaults to
+/// `@timestamp`, each group is searched concurrently with its own `timeout`
+/// falling back to `freshness` subsystem timeout
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawFreshnessConfig")]
+pub struct FreshnessConfig {
+    name: String,
+    indices: Vec<String>,
+    field: String,
+    timeout: Option<Duration>,
+}
+
+impl TryFrom<RawFreshnessConfig> for FreshnessConfig {
+    type Error = String;
+
+    fn try_from(raw: RawFreshnessConfig) -> Result<Self, Self::Error> {
+        if raw.name.is_empty() {
+            return Err("freshness group requires `name`".into());
+        }
+
+        let indices = raw
+            .index
+            .split(',')
+            .map(str::trim)
+            .filter(|index| !index.is_empty())
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        if indices.is_empty() {
+            return Err(format!("freshness group `{}` requires `index`", raw.name));
+        }
+
+        let timeout = raw
+            .timeout
+            .map(|timeout| {
+                timeout.parse::<humantime::Duration>().map_err(|e| {
+                    format!(
+                        "freshness group `{}` invalid timeout `{}` err {}",
+                        raw.name, timeout, e
+                    )
+                })
+            })
+            .transpose()?
+            .map(Into::into);
+
+        Ok(Self {
+            name: raw.name,
+            indices,
+            field: raw.field.unwrap_or_else(|| DEFAULT_FIELD.into()),
+            timeout,
+        })
+    }
+}
+
+impl FreshnessConfig {
+    /// Group name exported as `group` label
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Searched indices, index patterns or data streams
+    pub fn indices(&self) -> Vec<&str> {
+        self.indices.iter().map(String::as_str).collect()
+    }
+
+    /// Timestamp field of latest document
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Search timeout of the group, if not set subsystem timeout is used
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Search request body of latest document timestamp
+    pub fn body(&self) -> Value {
+        json!({
+            "size": 0,
+            "track_total_hits": false,
+            "aggs": {"latest": {"max": {"field": self.field}}}
+        })
+    }
+
+    /// Metric value of search response, `None` in case no document has
+    /// timestamp field
+    ///
+    /// Lag is not negative, documents timestamped in the future are
+    /// considered as just ingested
+    pub fn value(&self, response: &Value, now_millis: i64) -> Option<Value> {
+        let latest = response["aggregations"]["latest"]["value"].as_f64()? as i64;
+
+        Some(json!({
+            "group": self.name,
+            "index": self.indices.join(","),
+            "index_latest_document_timestamp_millis": latest,
+            "index_ingestion_lag_millis": (now_millis - latest).max(0),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freshness_config() {
+        let config: FreshnessConfig = serde_json::from_str(
+            r#"{"name": "nginx", "index": "logs-nginx-*, logs-ingress-*", "timeout": "5s"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.name(), "nginx");
+        assert_eq!(config.indices(), vec!["logs-nginx-*", "logs-ingress-*"]);
+        assert_eq!(config.field(), "@timestamp");
+        assert_eq!(config.timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(
+            config.body()["aggs"]["latest"]["max"]["field"],
+            "@timestamp"
+        );
+
+        assert!(serde_json::from_str::<FreshnessConfig>(r#"{"name": "x", "index": ""}"#).is_err());
+        assert!(serde_json::from_str::<FreshnessConfig>(
+            r#"{"name": "x", "index": "logs-*", "timeout": "soon"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_freshness_value() {
+        let config: FreshnessConfig = serde_json::from_str(
+            r#"{"name": "nginx", "index": "logs-nginx-*", "field": "event.ingested"}"#,
+        )
+        .unwrap();
+
+        let response = json!({
+            "took": 3,
+            "timed_out": false,
+            "hits": {"hits": []},
+            "aggregations": {
+                "latest": {"value": 1700000000000.0, "value_as_string": "2023-11-14T22:13:20.000Z"}
+            }
+        });
+
+        assert_eq!(
+            config.value(&response, 1700000090000),
+            Some(json!({
+                "group": "nginx",
+                "index": "logs-nginx-*",
+                "index_latest_document_timestamp_millis": 1700000000000_i64,
+                "index_ingestion_lag_millis": 90000,
+            }))
+        );
+
+        assert_eq!(
+            config.value(&response, 1690000000000).unwrap()["index_ingestion_lag_millis"],
+            0
+        );
+
+        // Milliseconds are exported as seconds
+        let metrics =
+            crate::metric::from_values(vec![config.value(&response, 1700000090000).unwrap()]);
+        assert!(metrics[0]
+            .iter()
+            .any(|metric| metric.key() == "index_latest_document_timestamp_seconds"));
+        assert!(metrics[0]
+            .iter()
+            .any(|metric| metric.key() == "index_ingestion_lag_seconds"));
+
+        let empty = json!({"aggregations": {"latest": {"value": null}}});
+        assert_eq!(config.value(&empty, 1700000090000), None);
+    }
+
+    #[test]
+    fn test_freshness_collection() {
+        use crate::collection::Collection;
+
+        let response = |latest: i64| json!({"aggregations": {"latest": {"value": latest}}});
+
+        let values = [("nginx", "logs-nginx-*"), ("api", "logs-api-*")]
+            .iter()
+            .map(|(name, index)| {
+                serde_json::from_value::<FreshnessConfig>(json!({"name": name, "index": index}))
+                    .unwrap()
+            })
+            .zip([1700000000000, 1700000060000])
+            .filter_map(|(config, latest)| config.value(&response(latest), 1700000090000))
+            .collect();
+
+        let mut collection = Collection::new("test_freshness", Default::default());
+        collection.include_labels = vec!["group".into(), "index".into()];
+
+        for metrics in crate::metric::from_values(values) {
+            collection.collect(metrics).unwrap();
+        }
+
+        // Each group is a series of its own
+        let lag = &collection.fgauges["index_ingestion_lag_seconds"];
+        assert_eq!(
+            lag.with_label_values(&["nginx", "logs-nginx-*"]).get(),
+            90.0
+        );
+        assert_eq!(lag.with_label_values(&["api", "logs-api-*"]).get(), 30.0);
+        assert_eq!(collection.series(), 4);
+    }
+}
@@ -39,11 +39,14 @@ extern crate serde_derive;
 use elasticsearch::cert::{Certificate, CertificateValidation};
 use elasticsearch::http::transport::{SingleNodeConnectionPool, TransportBuilder};
 use elasticsearch::Elasticsearch;
-use prometheus::{default_registry, HistogramOpts, HistogramVec, IntGaugeVec, Opts};
+use prometheus::proto::MetricFamily;
+use prometheus::{
+    default_registry, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+};
 use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::Read;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 /// Generic collector of Elasticsearch metrics
@@ -51,6 +54,10 @@ pub mod collection;
 /// Metric
 pub mod metric;
 
+/// Metrics exposition formats: Prometheus text, OpenMetrics and protobuf
+pub mod exposition;
+
+mod config;
 mod options;
 pub use options::{CertificateValidationOptions, ExporterOptions};
 
@@ -62,6 +69,21 @@ pub mod metadata;
 
 pub(crate) mod metrics;
 
+mod on_demand;
+mod push;
+
+/// Metric sinks besides Prometheus registry: DogStatsD, Graphite
+pub mod sink;
+
+/// Custom search query subsystems
+pub mod query;
+
+/// User defined REST endpoint subsystems
+pub mod endpoint;
+
+/// Index freshness of latest ingested documents
+pub mod freshness;
+
 /// Labels type with ordered keys
 pub type Labels = BTreeMap<String, String>;
 
@@ -71,9 +93,21 @@ pub type CollectionLabels = BTreeMap<String, Vec<String>>;
 /// Exporter polling intervals
 pub type ExporterPollIntervals = HashMap<String, Duration>;
 
+/// Exporter max series per subsystem
+pub type ExporterMaxSeries = BTreeMap<String, usize>;
+
 /// Exporter metrics switch ON/OFF
 pub type ExporterMetricsSwitch = BTreeMap<String, bool>;
 
+/// Exporter metric type rules per subsystem
+pub type MetricTypeRules = BTreeMap<String, Vec<metric::MetricRule>>;
+
+/// Exporter push mode (remote_write, OTLP) HTTP headers
+pub type PushHeaders = BTreeMap<String, String>;
+
+/// Exporter relabel configs per subsystem
+pub type RelabelConfigs = BTreeMap<String, Vec<collection::relabel::RelabelConfig>>;
+
 /// Elasticsearch exporter
 #[derive(Debug, Clone)]
 pub struct Exporter(Arc<Inner>);
@@ -95,6 +129,18 @@ struct Inner {
 
     // Exporter metrics
     metrics: ExporterMetrics,
+
+    /// Subsystem last successful poll completion timestamp in milliseconds
+    last_collect: RwLock<HashMap<&'static str, i64>>,
+
+    /// Sinks collected metrics are emitted to besides Prometheus registry
+    sinks: Vec<Arc<dyn sink::Sink>>,
+
+    /// On-demand collection of subsystems triggered by scrape
+    on_demand: on_demand::OnDemand,
+
+    /// Series dropped by max series limit by subsystem and metric key
+    series_dropped: RwLock<HashMap<(&'static str, String), u64>>,
 }
 
 /// Global metrics for Elasticsearch exporter
@@ -104,6 +150,10 @@ pub struct ExporterMetrics {
     subsystem_request_histogram: HistogramVec,
     /// Cluster health status
     cluster_health_status: IntGaugeVec,
+    /// Subsystem last successful collection timestamp
+    subsystem_last_collect_timestamp: GaugeVec,
+    /// Subsystem new series dropped by max series limit
+    series_dropped: IntCounterVec,
 }
 
 impl Exporter {
@@ -138,6 +188,118 @@ impl Exporter {
         &self.0.metrics
     }
 
+    /// Sinks collected metrics are emitted to besides Prometheus registry
+    pub fn sinks(&self) -> &[Arc<dyn sink::Sink>] {
+        &self.0.sinks
+    }
+
+    /// Set explicit sample timestamp of subsystem poll completion on metrics
+    /// of subsystems configured with sample timestamps
+    pub fn set_sample_timestamps(&self, families: &mut [MetricFamily]) {
+        for family in families.iter_mut() {
+            let Some(subsystem) = collection::registry::subsystem(family.get_name()) else {
+                continue;
+            };
+
+            if !self.options().is_sample_timestamped(subsystem) {
+                continue;
+            }
+
+            if let Some(timestamp_ms) = self.last_collect(subsystem) {
+                for metric in family.mut_metric().iter_mut() {
+                    metric.set_timestamp_ms(timestamp_ms);
+                }
+            }
+        }
+    }
+
+    /// Subscribe subsystem poller to on-demand collection requests
+    pub(crate) fn on_demand_subscribe(
+        &self,
+        subsystem: &'static str,
+    ) -> tokio::sync::mpsc::Receiver<on_demand::Request> {
+        self.0.on_demand.subscribe(subsystem)
+    }
+
+    /// Collect subsystems matching filter concurrently, e.g.: on scrape,
+    /// bounded by subsystem timeout, no-op unless on-demand mode is enabled
+    pub async fn collect(&self, filter: impl Fn(&str) -> bool) {
+        let options = self.options();
+
+        if !options.exporter_on_demand {
+            return;
+        }
+
+        self.0
+            .on_demand
+            .collect(
+                filter,
+                options.exporter_on_demand_min_interval,
+                |subsystem| options.timeout_for_subsystem(subsystem),
+            )
+            .await
+    }
+
+    /// Subsystem last successful poll completion timestamp in milliseconds
+    pub fn last_collect(&self, subsystem: &str) -> Option<i64> {
+        self.0
+            .last_collect
+            .read()
+            .expect("last collect lock")
+            .get(subsystem)
+            .copied()
+    }
+
+    /// Record subsystem poll completion timestamp
+    pub(crate) fn set_last_collect(&self, subsystem: &'static str, timestamp_ms: i64) {
+        self.metrics()
+            .subsystem_last_collect_timestamp
+            .with_label_values(&[subsystem, self.cluster_name()])
+            .set(timestamp_ms as f64 / 1000.0);
+
+        let _ = self
+            .0
+            .last_collect
+            .write()
+            .expect("last collect lock")
+            .insert(subsystem, timestamp_ms);
+    }
+
+    /// Record subsystem series dropped by max series limit by metric key
+    pub(crate) fn add_series_dropped(
+        &self,
+        subsystem: &'static str,
+        dropped: HashMap<String, u64>,
+    ) {
+        self.metrics()
+            .series_dropped
+            .with_label_values(&[subsystem, self.cluster_name()])
+            .inc_by(dropped.values().sum());
+
+        let mut series_dropped = self.0.series_dropped.write().expect("series dropped lock");
+
+        for (key, count) in dropped.into_iter() {
+            *series_dropped.entry((subsystem, key)).or_default() += count;
+        }
+    }
+
+    /// Top metric keys by series dropped by max series limit since start,
+    /// e.g.: `[("cat_shards", "docs", 5000)]`
+    pub fn series_dropped_top(&self, limit: usize) -> Vec<(&'static str, String, u64)> {
+        let mut top = self
+            .0
+            .series_dropped
+            .read()
+            .expect("series dropped lock")
+            .iter()
+            .map(|((subsystem, key), count)| (*subsystem, key.clone(), *count))
+            .collect::<Vec<_>>();
+
+        top.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (a.0, &a.1).cmp(&(b.0, &b.1))));
+        top.truncate(limit);
+        top
+    }
+
     /// Spawn exporter
     pub async fn new(options: ExporterOptions) -> Result<Self, Box<dyn std::error::Error>> {
         let connection_pool = SingleNodeConnectionPool::new(options.elasticsearch_url.clone());
@@ -207,10 +369,34 @@ impl Exporter {
                 &["cluster", "color"],
             )
             .expect("valid prometheus metric"),
+
+            subsystem_last_collect_timestamp: GaugeVec::new(
+                Opts::new(
+                    "subsystem_last_collect_timestamp_seconds",
+                    "Completion time of the last successful subsystem collection in seconds since epoch.",
+                )
+                .namespace(options.exporter_metrics_namespace.as_str()),
+                &["subsystem", "cluster"],
+            )
+            .expect("valid prometheus metric"),
+
+            series_dropped: IntCounterVec::new(
+                Opts::new(
+                    "series_dropped_total",
+                    "New series dropped by subsystem max series limit.",
+                )
+                .namespace(options.exporter_metrics_namespace.as_str()),
+                &["subsystem", "cluster"],
+            )
+            .expect("valid prometheus metric"),
         };
 
         default_registry().register(Box::new(metrics.cluster_health_status.clone()))?;
+        default_registry().register(Box::new(metrics.subsystem_last_collect_timestamp.clone()))?;
         default_registry().register(Box::new(metrics.subsystem_request_histogram.clone()))?;
+        default_registry().register(Box::new(metrics.series_dropped.clone()))?;
+
+        let sinks = sink::from_options(&options);
 
         Ok(Self(Arc::new(Inner {
             cluster_name,
@@ -219,6 +405,10 @@ impl Exporter {
             const_labels,
             nodes_metadata,
             metrics,
+            last_collect: Default::default(),
+            sinks,
+            on_demand: Default::default(),
+            series_dropped: Default::default(),
         })))
     }
 
@@ -228,6 +418,26 @@ impl Exporter {
         self.spawn_cluster();
         self.spawn_nodes();
         self.spawn_stats();
+        self.spawn_indices();
+        self.spawn_security();
+        self.spawn_queries();
+        self.spawn_endpoints();
+        self.spawn_freshness();
+
+        if self.options().exporter_remote_write_url.is_some() {
+            #[allow(clippy::let_underscore_future)]
+            let _ = tokio::spawn(push::remote_write::push(self.clone()));
+        }
+
+        if self.options().exporter_otlp_url.is_some() {
+            #[allow(clippy::let_underscore_future)]
+            let _ = tokio::spawn(push::otlp::push(self.clone()));
+        }
+
+        for sink in self.sinks().iter() {
+            #[allow(clippy::let_underscore_future)]
+            let _ = tokio::spawn(sink::flush(sink.clone()));
+        }
 
         if self.options().enable_metadata_refresh() {
             #[allow(clippy::let_underscore_future)]
@@ -239,6 +449,8 @@ impl Exporter {
         use metrics::_cluster::*;
 
         is_metric_enabled!(self.clone(), health);
+        is_metric_enabled!(self.clone(), settings);
+        is_metric_enabled!(self.clone(), watermarks);
     }
 
     fn spawn_stats(&self) {
@@ -247,12 +459,51 @@ impl Exporter {
         is_metric_enabled!(self.clone(), _all);
     }
 
+    fn spawn_security(&self) {
+        use metrics::_security::*;
+
+        is_metric_enabled!(self.clone(), certificates);
+        is_metric_enabled!(self.clone(), license);
+        is_metric_enabled!(self.clone(), authenticate);
+    }
+
+    fn spawn_indices(&self) {
+        use metrics::_indices::*;
+
+        is_metric_enabled!(self.clone(), settings);
+    }
+
+    fn spawn_queries(&self) {
+        for query in self.options().exporter_queries.iter() {
+            #[allow(clippy::let_underscore_future)]
+            let _ = tokio::spawn(metrics::_query::poll(self.clone(), Arc::new(query.clone())));
+        }
+    }
+
+    fn spawn_endpoints(&self) {
+        for endpoint in self.options().exporter_endpoints.iter() {
+            #[allow(clippy::let_underscore_future)]
+            let _ = tokio::spawn(metrics::_endpoint::poll(
+                self.clone(),
+                Arc::new(endpoint.clone()),
+            ));
+        }
+    }
+
+    fn spawn_freshness(&self) {
+        if !self.options().exporter_freshness.is_empty() {
+            #[allow(clippy::let_underscore_future)]
+            let _ = tokio::spawn(metrics::_freshness::poll(self.clone()));
+        }
+    }
+
     fn spawn_nodes(&self) {
         use metrics::_nodes::*;
 
         is_metric_enabled!(self.clone(), usage);
         is_metric_enabled!(self.clone(), stats);
         is_metric_enabled!(self.clone(), info);
+        is_metric_enabled!(self.clone(), hot_threads);
     }
 
     // =^.^=
@@ -277,6 +528,7 @@ impl Exporter {
 
         is_metric_enabled!(self.clone(), allocation);
         is_metric_enabled!(self.clone(), shards);
+        is_metric_enabled!(self.clone(), shards_summary);
         is_metric_enabled!(self.clone(), indices);
         is_metric_enabled!(self.clone(), segments);
         is_metric_enabled!(self.clone(), nodes);
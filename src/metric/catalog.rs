@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Description of well-known Elasticsearch field used for metric HELP and UNIT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Description {
+    /// Human readable description of a field
+    pub help: &'static str,
+    /// Unit of metric value after conversion, e.g.: seconds, bytes
+    pub unit: Option<&'static str>,
+}
+
+#[derive(Debug)]
+struct Field {
+    subsystem: &'static str,
+    /// Normalized metric key without unit postfix added by collection,
+    /// single `*` matches any part of the key, e.g.: thread pool name
+    key: &'static str,
+    description: Description,
+}
+
+const fn field(
+    subsystem: &'static str,
+    key: &'static str,
+    unit: Option<&'static str>,
+    help: &'static str,
+) -> Field {
+    Field {
+        subsystem,
+        key,
+        description: Description { help, unit },
+    }
+}
+
+const SECONDS: Option<&str> = Some("seconds");
+const BYTES: Option<&str> = Some("bytes");
+const PERCENT: Option<&str> = Some("percent");
+
+impl Field {
+    fn is_match(&self, subsystem: &str, key: &str) -> bool {
+        if self.subsystem != subsystem {
+            return false;
+        }
+
+        match self.key.split_once('*') {
+            Some((prefix, postfix)) => {
+                key.len() > prefix.len() + postfix.len()
+                    && key.starts_with(prefix)
+                    && key.ends_with(postfix)
+            }
+            None => self.key == key,
+        }
+    }
+}
+
+/// Embedded catalog of well-known `_cat` columns and `_nodes/stats` fields
+///
+/// https://www.elastic.co/guide/en/elasticsearch/reference/current/cat.html
+/// https://www.elastic.co/guide/en/elasticsearch/reference/current/cluster-nodes-stats.html
+#[rustfmt::skip]
+const CATALOG: &[Field] = &[
+    // _cat/health
+    field("cat_health", "node_total", None, "Number of nodes in the cluster"),
+    field("cat_health", "node_data", None, "Number of data nodes in the cluster"),
+    field("cat_health", "shards", None, "Number of active shards"),
+    field("cat_health", "pri", None, "Number of active primary shards"),
+    field("cat_health", "relo", None, "Number of relocating shards"),
+    field("cat_health", "init", None, "Number of initializing shards"),
+    field("cat_health", "unassign", None, "Number of unassigned shards"),
+    field("cat_health", "pending_tasks", None, "Number of pending cluster tasks"),
+    field("cat_health", "max_task_wait_time", SECONDS, "Wait time of the longest pending cluster task"),
+    field("cat_health", "active_shards_percent", PERCENT, "Ratio of active shards in the cluster"),
+    // _cat/indices
+    field("cat_indices", "pri", None, "Number of primary shards"),
+    field("cat_indices", "rep", None, "Number of replica shards"),
+    field("cat_indices", "docs_count", None, "Number of available documents"),
+    field("cat_indices", "docs_deleted", None, "Number of deleted documents"),
+    field("cat_indices", "store_size", BYTES, "Store size of all shards"),
+    field("cat_indices", "pri_store_size", BYTES, "Store size of primary shards"),
+    field("cat_indices", "dataset_size", BYTES, "Total size of the dataset"),
+    field("cat_indices", "indexing_index_total", None, "Number of indexing operations"),
+    field("cat_indices", "indexing_index_time", SECONDS, "Time spent in indexing"),
+    field("cat_indices", "indexing_index_failed", None, "Number of failed indexing operations"),
+    field("cat_indices", "indexing_delete_total", None, "Number of delete operations"),
+    field("cat_indices", "search_query_total", None, "Number of query operations"),
+    field("cat_indices", "search_query_time", SECONDS, "Time spent in query phase"),
+    field("cat_indices", "search_fetch_total", None, "Number of fetch operations"),
+    field("cat_indices", "search_fetch_time", SECONDS, "Time spent in fetch phase"),
+    field("cat_indices", "refresh_total", None, "Number of refreshes"),
+    field("cat_indices", "refresh_time", SECONDS, "Time spent in refreshes"),
+    field("cat_indices", "merges_total", None, "Number of completed merges"),
+    field("cat_indices", "merges_total_time", SECONDS, "Time spent in merges"),
+    field("cat_indices", "segments_count", None, "Number of segments"),
+    field("cat_indices", "segments_memory", BYTES, "Memory used by segments"),
+    field("cat_indices", "query_cache_memory_size", BYTES, "Memory used by query cache"),
+    field("cat_indices", "fielddata_memory_size", BYTES, "Memory used by fielddata"),
+    // _cat/nodes
+    field("cat_nodes", "heap_current", BYTES, "Used heap"),
+    field("cat_nodes", "heap_max", BYTES, "Maximum configured heap"),
+    field("cat_nodes", "heap_percent", PERCENT, "Used heap ratio"),
+    field("cat_nodes", "ram_current", BYTES, "Used total memory"),
+    field("cat_nodes", "ram_max", BYTES, "Total memory"),
+    field("cat_nodes", "ram_percent", PERCENT, "Used total memory ratio"),
+    field("cat_nodes", "cpu", PERCENT, "Recent system CPU usage"),
+    field("cat_nodes", "load_1m", None, "Load average of the last minute"),
+    field("cat_nodes", "load_5m", None, "Load average of the last five minutes"),
+    field("cat_nodes", "load_15m", None, "Load average of the last fifteen minutes"),
+    field("cat_nodes", "disk_total", BYTES, "Total disk space"),
+    field("cat_nodes", "disk_used", BYTES, "Used disk space"),
+    field("cat_nodes", "disk_avail", BYTES, "Available disk space"),
+    field("cat_nodes", "disk_used_percent", PERCENT, "Used disk space ratio"),
+    field("cat_nodes", "file_desc_current", None, "Used file descriptors"),
+    field("cat_nodes", "file_desc_max", None, "Maximum number of file descriptors"),
+    field("cat_nodes", "file_desc_percent", PERCENT, "Used file descriptors ratio"),
+    field("cat_nodes", "http_current_open", None, "Number of open HTTP connections"),
+    // _cat/shards
+    field("cat_shards", "docs", None, "Number of documents in shard"),
+    field("cat_shards", "store", BYTES, "Store size of shard"),
+    // _cat/allocation
+    field("cat_allocation", "shards", None, "Number of shards on node"),
+    field("cat_allocation", "disk_indices", BYTES, "Disk space used by node shards"),
+    field("cat_allocation", "disk_used", BYTES, "Total disk space used on node"),
+    field("cat_allocation", "disk_avail", BYTES, "Free disk space available to Elasticsearch"),
+    field("cat_allocation", "disk_total", BYTES, "Total capacity of all volumes"),
+    field("cat_allocation", "disk_percent", PERCENT, "Total ratio of disk space in use"),
+    // _cat/thread_pool
+    field("cat_thread_pool", "active", None, "Number of active threads"),
+    field("cat_thread_pool", "queue", None, "Number of tasks in queue"),
+    field("cat_thread_pool", "rejected", None, "Number of rejected tasks"),
+    field("cat_thread_pool", "completed", None, "Number of completed tasks"),
+    field("cat_thread_pool", "largest", None, "Highest number of active threads"),
+    field("cat_thread_pool", "pool_size", None, "Number of threads"),
+    // _cat/fielddata
+    field("cat_fielddata", "size", BYTES, "Memory used by fielddata"),
+    // _cat/segments
+    field("cat_segments", "docs_count", None, "Number of documents in segment"),
+    field("cat_segments", "docs_deleted", None, "Number of deleted documents in segment"),
+    field("cat_segments", "size", BYTES, "Disk space used by segment"),
+    field("cat_segments", "size_memory", BYTES, "Memory used by segment"),
+    // _cat/recovery
+    field("cat_recovery", "time", SECONDS, "Recovery time"),
+    field("cat_recovery", "files_percent", PERCENT, "Ratio of recovered files"),
+    field("cat_recovery", "bytes_percent", PERCENT, "Ratio of recovered bytes"),
+    field("cat_recovery", "bytes_total", BYTES, "Number of bytes to recover"),
+    // _nodes/stats indices
+    field("nodes_stats", "indices_docs_count", None, "Number of documents on node"),
+    field("nodes_stats", "indices_docs_deleted", None, "Number of deleted documents on node"),
+    field("nodes_stats", "indices_store_size_in_bytes", BYTES, "Store size of node shards"),
+    field("nodes_stats", "indices_store_reserved_in_bytes", BYTES, "Predicted store size of shards after ongoing recoveries"),
+    field("nodes_stats", "indices_indexing_index_total", None, "Number of indexing operations"),
+    field("nodes_stats", "indices_indexing_index_current", None, "Number of ongoing indexing operations"),
+    field("nodes_stats", "indices_indexing_index_failed", None, "Number of failed indexing operations"),
+    field("nodes_stats", "indices_indexing_index_time_in_seconds", SECONDS, "Time spent in indexing"),
+    field("nodes_stats", "indices_indexing_throttle_time_in_seconds", SECONDS, "Time spent in indexing throttling"),
+    field("nodes_stats", "indices_indexing_delete_total", None, "Number of delete operations"),
+    field("nodes_stats", "indices_get_total", None, "Number of get operations"),
+    field("nodes_stats", "indices_get_time_in_seconds", SECONDS, "Time spent in get operations"),
+    field("nodes_stats", "indices_search_query_total", None, "Number of query operations"),
+    field("nodes_stats", "indices_search_query_current", None, "Number of ongoing query operations"),
+    field("nodes_stats", "indices_search_query_time_in_seconds", SECONDS, "Time spent in query phase"),
+    field("nodes_stats", "indices_search_fetch_total", None, "Number of fetch operations"),
+    field("nodes_stats", "indices_search_fetch_time_in_seconds", SECONDS, "Time spent in fetch phase"),
+    field("nodes_stats", "indices_search_scroll_total", None, "Number of scroll operations"),
+    field("nodes_stats", "indices_search_open_contexts", None, "Number of open search contexts"),
+    field("nodes_stats", "indices_merges_total", None, "Number of completed merges"),
+    field("nodes_stats", "indices_merges_current", None, "Number of ongoing merges"),
+    field("nodes_stats", "indices_merges_total_time_in_seconds", SECONDS, "Time spent in merges"),
+    field("nodes_stats", "indices_merges_total_size_in_bytes", BYTES, "Size of merged segments"),
+    field("nodes_stats", "indices_refresh_total", None, "Number of refreshes"),
+    field("nodes_stats", "indices_refresh_total_time_in_seconds", SECONDS, "Time spent in refreshes"),
+    field("nodes_stats", "indices_flush_total", None, "Number of flushes"),
+    field("nodes_stats", "indices_flush_total_time_in_seconds", SECONDS, "Time spent in flushes"),
+    field("nodes_stats", "indices_query_cache_memory_size_in_bytes", BYTES, "Memory used by query cache"),
+    field("nodes_stats", "indices_query_cache_hit_count", None, "Number of query cache hits"),
+    field("nodes_stats", "indices_query_cache_miss_count", None, "Number of query cache misses"),
+    field("nodes_stats", "indices_query_cache_evictions", None, "Number of query cache evictions"),
+    field("nodes_stats", "indices_request_cache_memory_size_in_bytes", BYTES, "Memory used by request cache"),
+    field("nodes_stats", "indices_request_cache_hit_count", None, "Number of request cache hits"),
+    field("nodes_stats", "indices_request_cache_miss_count", None, "Number of request cache misses"),
+    field("nodes_stats", "indices_fielddata_memory_size_in_bytes", BYTES, "Memory used by fielddata"),
+    field("nodes_stats", "indices_fielddata_evictions", None, "Number of fielddata evictions"),
+    field("nodes_stats", "indices_segments_count", None, "Number of segments"),
+    field("nodes_stats", "indices_segments_memory_in_bytes", BYTES, "Memory used by segments"),
+    field("nodes_stats", "indices_translog_operations", None, "Number of translog operations"),
+    field("nodes_stats", "indices_translog_size_in_bytes", BYTES, "Size of translog"),
+    // _nodes/stats jvm
+    field("nodes_stats", "jvm_mem_heap_used_in_bytes", BYTES, "Memory used by heap"),
+    field("nodes_stats", "jvm_mem_heap_committed_in_bytes", BYTES, "Memory committed to heap"),
+    field("nodes_stats", "jvm_mem_heap_max_in_bytes", BYTES, "Maximum memory available to heap"),
+    field("nodes_stats", "jvm_mem_heap_used_percent", PERCENT, "Used heap ratio"),
+    field("nodes_stats", "jvm_mem_non_heap_used_in_bytes", BYTES, "Non-heap memory used"),
+    field("nodes_stats", "jvm_mem_non_heap_committed_in_bytes", BYTES, "Non-heap memory committed"),
+    field("nodes_stats", "jvm_mem_pools_*_used_in_bytes", BYTES, "Memory used by heap pool"),
+    field("nodes_stats", "jvm_mem_pools_*_max_in_bytes", BYTES, "Maximum memory of heap pool"),
+    field("nodes_stats", "jvm_threads_count", None, "Number of active JVM threads"),
+    field("nodes_stats", "jvm_threads_peak_count", None, "Highest number of JVM threads"),
+    field("nodes_stats", "jvm_gc_collectors_*_collection_count", None, "Number of garbage collections"),
+    field("nodes_stats", "jvm_gc_collectors_*_collection_time_in_seconds", SECONDS, "Time spent in garbage collections"),
+    field("nodes_stats", "jvm_buffer_pools_*_count", None, "Number of buffers in pool"),
+    field("nodes_stats", "jvm_buffer_pools_*_used_in_bytes", BYTES, "Memory used by buffer pool"),
+    // _nodes/stats os
+    field("nodes_stats", "os_cpu_percent", PERCENT, "Recent system CPU usage"),
+    field("nodes_stats", "os_cpu_load_average_1m", None, "Load average of the last minute"),
+    field("nodes_stats", "os_cpu_load_average_5m", None, "Load average of the last five minutes"),
+    field("nodes_stats", "os_cpu_load_average_15m", None, "Load average of the last fifteen minutes"),
+    field("nodes_stats", "os_mem_total_in_bytes", BYTES, "Total physical memory"),
+    field("nodes_stats", "os_mem_free_in_bytes", BYTES, "Free physical memory"),
+    field("nodes_stats", "os_mem_used_in_bytes", BYTES, "Used physical memory"),
+    field("nodes_stats", "os_mem_used_percent", PERCENT, "Used physical memory ratio"),
+    field("nodes_stats", "os_swap_total_in_bytes", BYTES, "Total swap space"),
+    field("nodes_stats", "os_swap_used_in_bytes", BYTES, "Used swap space"),
+    // _nodes/stats process
+    field("nodes_stats", "process_cpu_percent", PERCENT, "CPU usage of the process"),
+    field("nodes_stats", "process_cpu_total_in_seconds", SECONDS, "CPU time used by the process"),
+    field("nodes_stats", "process_open_file_descriptors", None, "Number of opened file descriptors"),
+    field("nodes_stats", "process_max_file_descriptors", None, "Maximum number of file descriptors"),
+    field("nodes_stats", "process_mem_total_virtual_in_bytes", BYTES, "Virtual memory guaranteed to the process"),
+    // _nodes/stats fs
+    field("nodes_stats", "fs_total_total_in_bytes", BYTES, "Total size of all file stores"),
+    field("nodes_stats", "fs_total_free_in_bytes", BYTES, "Unallocated disk space"),
+    field("nodes_stats", "fs_total_available_in_bytes", BYTES, "Disk space available to the JVM"),
+    field("nodes_stats", "fs_io_stats_total_read_bytes", BYTES, "Bytes read across all devices"),
+    field("nodes_stats", "fs_io_stats_total_write_bytes", BYTES, "Bytes written across all devices"),
+    // _nodes/stats thread_pool
+    field("nodes_stats", "thread_pool_*_threads", None, "Number of threads in thread pool"),
+    field("nodes_stats", "thread_pool_*_queue", None, "Number of tasks in thread pool queue"),
+    field("nodes_stats", "thread_pool_*_active", None, "Number of active threads in thread pool"),
+    field("nodes_stats", "thread_pool_*_rejected", None, "Number of rejected thread pool tasks"),
+    field("nodes_stats", "thread_pool_*_largest", None, "Highest number of active threads"),
+    field("nodes_stats", "thread_pool_*_completed", None, "Number of completed thread pool tasks"),
+    // _nodes/stats breakers
+    field("nodes_stats", "breakers_limit_size_bytes", BYTES, "Memory limit of circuit breaker"),
+    field("nodes_stats", "breakers_estimated_size_bytes", BYTES, "Estimated memory used by circuit breaker operation"),
+    field("nodes_stats", "breakers_tripped", None, "Number of times circuit breaker tripped"),
+    field("nodes_stats", "breakers_overhead", None, "Circuit breaker estimation multiplier"),
+    // _nodes/stats indexing_pressure
+    field("nodes_stats", "indexing_pressure_current_bytes", BYTES, "Memory used by in-flight indexing requests of stage"),
+    field("nodes_stats", "indexing_pressure_total_bytes", BYTES, "Memory used by indexing requests of stage since node start"),
+    field("nodes_stats", "indexing_pressure_rejections", None, "Number of indexing requests of stage rejected due to memory pressure"),
+    field("nodes_stats", "indexing_pressure_memory_limit_bytes", BYTES, "Memory limit of indexing requests"),
+    // _nodes/stats transport and http
+    field("nodes_stats", "transport_server_open", None, "Number of open inbound TCP connections"),
+    field("nodes_stats", "transport_rx_count", None, "Number of received RX packets"),
+    field("nodes_stats", "transport_rx_size_in_bytes", BYTES, "Size of received RX packets"),
+    field("nodes_stats", "transport_tx_count", None, "Number of sent TX packets"),
+    field("nodes_stats", "transport_tx_size_in_bytes", BYTES, "Size of sent TX packets"),
+    field("nodes_stats", "http_current_open", None, "Number of open HTTP connections"),
+    field("nodes_stats", "http_total_opened", None, "Number of opened HTTP connections"),
+    // _nodes/stats adaptive_selection
+    field("nodes_stats", "adaptive_selection_avg_queue_size", None, "Exponentially weighted moving average queue size of search requests on target node"),
+    field("nodes_stats", "adaptive_selection_avg_response_time_seconds", SECONDS, "Exponentially weighted moving average response time of search requests on target node"),
+    field("nodes_stats", "adaptive_selection_avg_service_time_seconds", SECONDS, "Exponentially weighted moving average service time of search requests on target node"),
+    field("nodes_stats", "adaptive_selection_outgoing_searches", None, "Number of outstanding search requests to target node"),
+    field("nodes_stats", "adaptive_selection_rank", None, "Rank of target node used for replica selection"),
+];
+
+/// Describe metric key of subsystem from embedded catalog
+pub fn describe(subsystem: &str, key: &str) -> Option<Description> {
+    CATALOG
+        .iter()
+        .find(|field| field.is_match(subsystem, key))
+        .map(|field| field.description)
+}
+
+/// Units of registered metrics by fully qualified metric name, used for
+/// OpenMetrics `# UNIT`
+static UNITS: LazyLock<RwLock<HashMap<String, &'static str>>> = LazyLock::new(Default::default);
+
+/// Unit of metric value after conversion by metric name postfix
+fn unit_from_name(fq_name: &str) -> Option<&'static str> {
+    let name = fq_name.strip_suffix("_total").unwrap_or(fq_name);
+
+    ["seconds", "bytes"]
+        .into_iter()
+        .find(|unit| name.ends_with(&format!("_{}", unit)))
+}
+
+/// Register unit of metric, OpenMetrics requires metric name to be postfixed
+/// with unit thus unit is not registered otherwise
+pub(crate) fn register_unit(fq_name: &str, description: Option<Description>) {
+    let name = fq_name.strip_suffix("_total").unwrap_or(fq_name);
+
+    let unit = description
+        .and_then(|description| description.unit)
+        .filter(|unit| name.ends_with(&format!("_{}", unit)))
+        .or_else(|| unit_from_name(fq_name));
+
+    if let Some(unit) = unit {
+        let _ = UNITS
+            .write()
+            .expect("units lock")
+            .insert(fq_name.to_string(), unit);
+    }
+}
+
+/// Unit of registered metric by fully qualified metric name
+pub fn unit(fq_name: &str) -> Option<&'static str> {
+    UNITS.read().expect("units lock").get(fq_name).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let description = describe("nodes_stats", "jvm_mem_heap_used_in_bytes").unwrap();
+        assert_eq!(description.help, "Memory used by heap");
+        assert_eq!(description.unit, Some("bytes"));
+
+        let description = describe("nodes_stats", "thread_pool_write_rejected").unwrap();
+        assert_eq!(description.help, "Number of rejected thread pool tasks");
+
+        assert!(describe("nodes_stats", "thread_pool__rejected").is_none());
+        assert!(describe("cat_indices", "jvm_mem_heap_used_in_bytes").is_none());
+        assert!(describe("cat_indices", "unknown").is_none());
+
+        register_unit(
+            "elasticsearch_cat_indices_store_size_bytes",
+            describe("cat_indices", "store_size"),
+        );
+        assert_eq!(
+            unit("elasticsearch_cat_indices_store_size_bytes"),
+            Some("bytes")
+        );
+
+        // Unit must be metric name postfix
+        register_unit(
+            "elasticsearch_nodes_stats_jvm_mem_heap_used_percent",
+            describe("nodes_stats", "jvm_mem_heap_used_percent"),
+        );
+        assert_eq!(
+            unit("elasticsearch_nodes_stats_jvm_mem_heap_used_percent"),
+            Some("percent")
+        );
+
+        register_unit(
+            "elasticsearch_cat_health_active_shards_percent_as_number",
+            None,
+        );
+        assert!(unit("elasticsearch_cat_health_active_shards_percent_as_number").is_none());
+
+        register_unit("elasticsearch_nodes_stats_gc_seconds_total", None);
+        assert_eq!(
+            unit("elasticsearch_nodes_stats_gc_seconds_total"),
+            Some("seconds")
+        );
+    }
+}
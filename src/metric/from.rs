@@ -1,62 +1,26 @@
 use serde_json::Value;
+use std::collections::HashMap;
 
-use super::{Metric, MetricError, Metrics};
+use super::{Histogram, Metric, MetricError, MetricKey, MetricRule, MetricType, Metrics};
 use crate::reserved;
 
-/// Map from key and JSON value
-type SerdeMap = serde_json::Map<String, Value>;
+/// Interned keys are dropped once exceeded, e.g.: keys containing index names
+const MAX_INTERNED_KEYS: usize = 65_536;
 
 /// Build vector of metrics from JSON vector values
 pub fn from_values(values: Vec<Value>) -> Vec<Metrics> {
-    let mut metrics: Vec<Metrics> = Vec::new();
-
-    for value in values.into_iter() {
-        metrics.extend(from_value(value));
-    }
+    from_values_with_rules(values, &[])
+}
 
-    metrics
+/// Build vector of metrics from JSON vector values, metric type rules
+/// are consulted before built-in rules
+pub fn from_values_with_rules(values: Vec<Value>, rules: &[MetricRule]) -> Vec<Metrics> {
+    Flattener::default().flatten(&values, rules)
 }
 
 /// Build metric from JSON value
 pub fn from_value(value: Value) -> Vec<Metrics> {
-    let mut output: Vec<Metrics> = Vec::new();
-
-    // Instead of returning error print error and return
-    // any metrics that were processed
-    match _from_value("", &mut output, &value) {
-        Ok(metrics) => {
-            debug_assert!(metrics.is_empty());
-        }
-        Err(e) => {
-            error!("from_value err {}", e);
-        }
-    }
-
-    output
-}
-
-fn _from_value(
-    prefix: &str,
-    output: &mut Vec<Metrics>,
-    value: &Value,
-) -> Result<Metrics, MetricError> {
-    let mut metrics = Metrics::new();
-
-    if value.is_number()
-        || value.is_boolean()
-        || value.is_string()
-        || value.is_number()
-        || value.is_null()
-    {
-        metrics.push(Metric::try_from((prefix, value))?);
-    } else if let Some(obj) = value.as_object() {
-        _from_map(prefix, output, obj)?;
-    } else if let Some(array) = value.as_array() {
-        from_array(prefix, output, array)?;
-    } else {
-        unreachable!()
-    }
-    Ok(metrics)
+    Flattener::default().flatten(std::slice::from_ref(&value), &[])
 }
 
 // Skip prefix append is required to skip prefixing for the keys
@@ -84,46 +48,168 @@ const SKIP_PREFIX_APPEND: &[&str] = &[
     "index",
 ];
 
-fn _from_map(prefix: &str, output: &mut Vec<Metrics>, map: &SerdeMap) -> Result<(), MetricError> {
-    let mut metrics = Metrics::new();
+/// Flattens JSON values into metric groups, each JSON object (or array) having
+/// scalar values yields a group. Flattened key is built in a single reusable
+/// buffer and metric keys are parsed once, interned along with matching metric
+/// type rules, thus flattener should be kept across polls of a subsystem.
+/// Responses are still deserialized into `serde_json::Value` first, as
+/// subsystems rewrite values (e.g. node labels injection) before flattening
+#[derive(Debug, Default)]
+pub struct Flattener {
+    /// Flattened key of currently walked value, prefix is `buffer[start..]`
+    buffer: String,
+    /// Parsed metric keys by flattened key
+    keys: HashMap<Box<str>, MetricKey>,
+    /// Rules interned keys were matched against
+    rules: Vec<MetricRule>,
+}
 
-    for (key, value) in map.iter() {
-        trace!("_from_map PREFIX {} K {}", prefix, key);
+impl Flattener {
+    /// Flatten JSON values into metric groups, metric type rules are consulted
+    /// before built-in rules
+    pub fn flatten(&mut self, values: &[Value], rules: &[MetricRule]) -> Vec<Metrics> {
+        if self.rules[..] != *rules || self.keys.len() > MAX_INTERNED_KEYS {
+            self.keys.clear();
+            self.rules = rules.to_vec();
+        }
 
-        let inner_metrics = if prefix.is_empty() || SKIP_PREFIX_APPEND.contains(&key.as_str()) {
-            _from_value(key, output, value)?
-        } else {
-            _from_value(&format!("{}_{}", prefix, key), output, value)?
-        };
+        let mut output: Vec<Metrics> = Vec::new();
+
+        for value in values.iter() {
+            self.buffer.clear();
 
-        if !inner_metrics.is_empty() {
-            metrics.extend(inner_metrics);
+            // Top level scalars have no key thus are skipped
+            let mut metrics = Metrics::new();
+
+            // Instead of returning error print error and return
+            // any metrics that were processed
+            if let Err(e) = self.value(0, value, &mut output, &mut metrics, rules) {
+                error!("from_value err {}", e);
+            }
         }
+
+        output
     }
 
-    if !metrics.is_empty() {
-        output.push(metrics);
+    /// Walk value of key `buffer[start..]`, scalar metric is pushed into
+    /// metrics of parent object
+    fn value(
+        &mut self,
+        start: usize,
+        value: &Value,
+        output: &mut Vec<Metrics>,
+        metrics: &mut Metrics,
+        rules: &[MetricRule],
+    ) -> Result<(), MetricError> {
+        match value {
+            Value::Object(map) => self.map(start, map, output, rules),
+            Value::Array(array) => self.array(start, array, output, rules),
+            _ => {
+                metrics.push(self.metric(start, value, rules)?);
+                Ok(())
+            }
+        }
     }
 
-    Ok(())
-}
+    fn map(
+        &mut self,
+        start: usize,
+        map: &serde_json::Map<String, Value>,
+        output: &mut Vec<Metrics>,
+        rules: &[MetricRule],
+    ) -> Result<(), MetricError> {
+        let mut metrics = Metrics::new();
 
-fn from_array(
-    prefix: &str,
-    output: &mut Vec<Metrics>,
-    values: &[Value],
-) -> Result<(), MetricError> {
-    let mut metrics = Metrics::new();
+        for (key, value) in map.iter() {
+            trace!("_from_map PREFIX {} K {}", &self.buffer[start..], key);
 
-    for value in values.iter() {
-        metrics.extend(_from_value(prefix, output, value)?);
+            let len = self.buffer.len();
+
+            let start = if start == len || SKIP_PREFIX_APPEND.contains(&key.as_str()) {
+                len
+            } else {
+                self.buffer.push('_');
+                start
+            };
+            self.buffer.push_str(key);
+
+            let result = self.value(start, value, output, &mut metrics, rules);
+
+            self.buffer.truncate(len);
+            result?;
+        }
+
+        if !metrics.is_empty() {
+            output.push(metrics);
+        }
+
+        Ok(())
     }
 
-    if !metrics.is_empty() {
-        output.push(metrics);
+    fn array(
+        &mut self,
+        start: usize,
+        values: &[Value],
+        output: &mut Vec<Metrics>,
+        rules: &[MetricRule],
+    ) -> Result<(), MetricError> {
+        let mut metrics = Metrics::new();
+
+        // Bucketed arrays, e.g.: [{"lt_millis": 1, "count": 2}, {"ge_millis": 1, "count": 0}]
+        // are exported as single histogram instead of `_count` gauges per bucket
+        if let Some(histogram) = Histogram::from_buckets(values) {
+            // Labels injected into each bucket (node name, ip, etc.) are kept along histogram
+            if let Some(map) = values.first().and_then(Value::as_object) {
+                for (key, value) in map.iter().filter(|(_, value)| value.is_string()) {
+                    let len = self.buffer.len();
+                    self.buffer.push_str(key);
+
+                    let metric = self.metric(len, value, rules);
+
+                    self.buffer.truncate(len);
+                    metrics.push(metric?);
+                }
+            }
+
+            metrics.push(Metric::from_histogram(&self.buffer[start..], histogram));
+            output.push(metrics);
+
+            return Ok(());
+        }
+
+        for value in values.iter() {
+            self.value(start, value, output, &mut metrics, rules)?;
+        }
+
+        if !metrics.is_empty() {
+            output.push(metrics);
+        }
+
+        Ok(())
     }
 
-    Ok(())
+    /// Metric of scalar value with key `buffer[start..]`
+    fn metric(
+        &mut self,
+        start: usize,
+        value: &Value,
+        rules: &[MetricRule],
+    ) -> Result<Metric, MetricError> {
+        let raw = &self.buffer[start..];
+
+        let key = match self.keys.get(raw) {
+            Some(key) => key,
+            None => self
+                .keys
+                .entry(raw.into())
+                .or_insert_with(|| MetricKey::new(raw, rules)),
+        };
+
+        Ok(Metric(
+            key.key.to_string(),
+            MetricType::from_rule_types((&key.last, value), key.rule_types.iter().copied())?,
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -173,4 +259,62 @@ mod tests {
             expected
         );
     }
+
+    #[test]
+    fn test_histogram_from_array() {
+        let value: Value = serde_json::from_str(include_str!("../tests/files/histograms.json"))
+            .expect("valid json");
+
+        let metrics = from_value(value);
+
+        let histogram = metrics
+            .iter()
+            .find(|m| {
+                m.iter()
+                    .any(|m| m.key() == "transport_inbound_handling_time_seconds")
+            })
+            .expect("transport histogram");
+
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(
+            histogram[0],
+            Metric(
+                "name".into(),
+                MetricType::Label("m1-nodename.example.com".into())
+            )
+        );
+        assert!(matches!(histogram[1].metric_type(), MetricType::Histogram(h) if h.count() == 14));
+
+        assert!(metrics
+            .iter()
+            .flatten()
+            .any(|m| m.key() == "http_routes___cat_nodes_requests_size_bytes"
+                && matches!(m.metric_type(), MetricType::Histogram(_))));
+
+        // Buckets are not flattened into gauges
+        assert!(!metrics.iter().flatten().any(|m| m
+            .key()
+            .starts_with("transport_inbound_handling_time_histogram")));
+    }
+
+    #[test]
+    fn test_flattener_rules_cache() {
+        let value = serde_json::json!({"indices": {"query_cache": {"cache_size": 10}}});
+        let rules = |rules: &[&str]| -> Vec<MetricRule> {
+            rules.iter().map(|rule| rule.parse().unwrap()).collect()
+        };
+
+        let mut flattener = Flattener::default();
+
+        let metrics = flattener.flatten(std::slice::from_ref(&value), &rules(&["*_size:bytes"]));
+        assert_eq!(metrics[0][0].metric_type(), &MetricType::Bytes(10));
+
+        // Equal rules of another allocation keep interned keys
+        let _ = flattener.flatten(std::slice::from_ref(&value), &rules(&["*_size:bytes"]));
+        assert_eq!(flattener.keys.len(), 1);
+
+        // Changed rules are matched again
+        let metrics = flattener.flatten(std::slice::from_ref(&value), &rules(&["*_size:float"]));
+        assert_eq!(metrics[0][0].metric_type(), &MetricType::GaugeF(10.0));
+    }
 }
@@ -0,0 +1,159 @@
+use serde_json::Value;
+
+/// Histogram built from Elasticsearch bucketed arrays, e.g.: `/_nodes/stats`
+/// `transport.inbound_handling_time_histogram` or `http.routes.*.size_histogram`
+///
+/// [{"lt_millis": 1, "count": 10}, {"ge_millis": 1, "lt_millis": 2, "count": 3}, {"ge_millis": 2, "count": 1}]
+///
+/// Elasticsearch reports per bucket counts, these are converted to cumulative
+/// Prometheus buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Bucket upper bound (converted to base unit) and cumulative count,
+    /// +Inf bucket is not included
+    buckets: Vec<(f64, u64)>,
+    /// Total number of observations
+    count: u64,
+    /// Elasticsearch does not report sum of observations, sum is a lower
+    /// bound estimate from bucket lower bounds
+    sum: f64,
+    /// Metric key postfix of bucket unit, e.g.: _seconds, _bytes
+    postfix: &'static str,
+}
+
+impl Histogram {
+    /// Cumulative buckets of upper bound and count
+    pub fn buckets(&self) -> &[(f64, u64)] {
+        &self.buckets
+    }
+
+    /// Total count of observations
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Lower bound estimate of sum of observations, each observation is
+    /// counted as lower bound of its bucket
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Metric key postfix of bucket unit
+    pub fn postfix(&self) -> &'static str {
+        self.postfix
+    }
+
+    /// Try to build histogram from array of bucket objects, all objects must have
+    /// `count` and at least one of `lt_<unit>` or `ge_<unit>` bounds. String
+    /// values are tolerated as these are labels injected by exporter
+    pub(crate) fn from_buckets(values: &[Value]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut unit: Option<&str> = None;
+        let mut bounds: Vec<(Option<f64>, Option<f64>, u64)> = Vec::with_capacity(values.len());
+
+        for value in values.iter() {
+            let map = value.as_object()?;
+
+            let mut count: Option<u64> = None;
+            let mut lower: Option<f64> = None;
+            let mut upper: Option<f64> = None;
+
+            for (key, value) in map.iter() {
+                if key == "count" {
+                    count = Some(value.as_u64()?);
+                    continue;
+                }
+
+                let (bound, bound_unit) = match key.split_once('_') {
+                    Some((bound @ ("lt" | "ge"), bound_unit)) => (bound, bound_unit),
+                    _ if value.is_string() => continue,
+                    _ => return None,
+                };
+
+                if *unit.get_or_insert(bound_unit) != bound_unit {
+                    return None;
+                }
+
+                if bound == "lt" {
+                    upper = Some(value.as_f64()?);
+                } else {
+                    lower = Some(value.as_f64()?);
+                }
+            }
+
+            if lower.is_none() && upper.is_none() {
+                return None;
+            }
+
+            bounds.push((lower, upper, count?));
+        }
+
+        let (scale, postfix) = match unit? {
+            "millis" => (1e-3, "_seconds"),
+            "micros" => (1e-6, "_seconds"),
+            "nanos" => (1e-9, "_seconds"),
+            "bytes" => (1.0, "_bytes"),
+            _ => (1.0, ""),
+        };
+
+        // Buckets are not guaranteed to be ordered, open ended (+Inf) bucket goes last
+        bounds.sort_by(|a, b| {
+            a.1.unwrap_or(f64::INFINITY)
+                .total_cmp(&b.1.unwrap_or(f64::INFINITY))
+        });
+
+        let mut buckets = Vec::with_capacity(bounds.len());
+        let mut count: u64 = 0;
+        let mut sum: f64 = 0.0;
+
+        for (lower, upper, bucket_count) in bounds.into_iter() {
+            count += bucket_count;
+
+            // First bucket is bounded by zero
+            sum += lower.unwrap_or_default() * scale * bucket_count as f64;
+
+            if let Some(upper) = upper {
+                buckets.push((upper * scale, count));
+            }
+        }
+
+        Some(Self {
+            buckets,
+            count,
+            sum,
+            postfix,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_from_buckets() {
+        let value: Value = serde_json::from_str(include_str!("../tests/files/histograms.json"))
+            .expect("valid json");
+
+        let buckets = value["transport"]["inbound_handling_time_histogram"]
+            .as_array()
+            .unwrap();
+
+        let histogram = Histogram::from_buckets(buckets).unwrap();
+        assert_eq!(histogram.postfix(), "_seconds");
+        assert_eq!(histogram.count(), 14);
+        assert_eq!(
+            histogram.buckets(),
+            &[(0.001, 10), (0.002, 13), (0.004, 13)]
+        );
+        // Lower bound estimate: 10 * 0 + 3 * 1ms + 0 * 2ms + 1 * 4ms
+        assert!((histogram.sum() - (3.0 * 0.001 + 0.004)).abs() < 1e-9);
+
+        // Not a histogram: object without bounds
+        let value: Value = serde_json::from_str(r#"[{"count": 1, "total": 2}]"#).unwrap();
+        assert!(Histogram::from_buckets(value.as_array().unwrap()).is_none());
+    }
+}
@@ -3,22 +3,27 @@ use serde_json::Value;
 use std::str::FromStr;
 use std::time::Duration;
 
-use super::{MetricError, RawMetric};
+use super::rules::{MetricRule, NumberParse, RuleType, DEFAULT_RULES};
+use super::{Histogram, MetricError, RawMetric};
 
 /// Parsed metric types
 #[derive(Debug, PartialEq)]
 pub enum MetricType {
-    /// Time is parsed as duration in milliseconds
+    /// Time is parsed as duration in milliseconds, microseconds or nanoseconds
     /// duration is casted to float seconds
-    Time(Duration), // Milliseconds
+    Time(Duration),
     /// Bytes
     Bytes(i64),
     /// Integer gauges
     Gauge(i64),
+    /// Monotonic counters
+    Counter(i64),
     /// Float gauges
     GaugeF(f64),
     /// Switch metrics having value of true/false
     Switch(u8),
+    /// Histogram from Elasticsearch bucketed arrays
+    Histogram(Histogram),
 
     /// Labels e.g.: index, node, ip, etc.
     Label(String), // Everything not number
@@ -31,13 +36,90 @@ impl TryFrom<RawMetric<'_>> for MetricType {
     type Error = MetricError;
 
     fn try_from(metric: RawMetric) -> Result<Self, MetricError> {
+        Self::from_rules(metric.0, metric, &[])
+    }
+}
+
+impl MetricType {
+    /// Parse metric type of last key segment by consulting user rules on full key
+    /// first and falling back to built-in rules
+    pub(crate) fn from_rules(
+        key: &str,
+        metric: RawMetric,
+        rules: &[MetricRule],
+    ) -> Result<Self, MetricError> {
+        Self::from_rule_types(
+            metric,
+            rules
+                .iter()
+                .chain(DEFAULT_RULES.iter())
+                .filter(|rule| rule.is_match(key, metric.0))
+                .map(|rule| (rule.rule_type(), rule.number_parse())),
+        )
+    }
+
+    /// Parse metric type by types of rules matching metric key in order of
+    /// precedence
+    pub(crate) fn from_rule_types(
+        metric: RawMetric,
+        rule_types: impl Iterator<Item = (RuleType, NumberParse)>,
+    ) -> Result<Self, MetricError> {
         let value: &Value = metric.1;
 
+        if value.is_boolean() {
+            return Ok(MetricType::Switch(if value.as_bool().unwrap_or(false) {
+                1
+            } else {
+                0
+            }));
+        }
+
+        // Handling cases when value is null or "-" aka not initialized
+        if value.is_null() || value.as_str().map(|v| v == "-").unwrap_or(false) {
+            return Ok(MetricType::Null);
+        }
+
+        for (rule_type, number_parse) in rule_types {
+            if let Some(metric_type) = Self::parse(rule_type, number_parse, metric)? {
+                return Ok(metric_type);
+            }
+        }
+
+        if cfg!(debug_assertions) {
+            println!("Catchall metric: {:?}", metric);
+
+            let parsed = value.as_str().and_then(|v| v.parse::<i64>().ok());
+
+            if parsed.is_some() {
+                println!("Unhandled metic value {:?}", metric);
+            }
+        }
+
+        Ok(MetricType::Label(
+            value
+                .as_str()
+                .ok_or_else(|| MetricError::unknown(metric.0.to_owned(), Some(value.clone())))?
+                .to_owned(),
+        ))
+    }
+
+    /// Parse value as given rule type, None is returned when rule is not applicable
+    fn parse(
+        rule_type: RuleType,
+        number_parse: NumberParse,
+        metric: RawMetric,
+    ) -> Result<Option<Self>, MetricError> {
+        let value: &Value = metric.1;
+        let lenient = number_parse == NumberParse::Lenient;
+
         let unknown = || MetricError::unknown(metric.0.to_owned(), Some(value.clone()));
 
         let parse_i64 = || -> Result<i64, MetricError> {
             if value.is_number() {
-                Ok(value.as_i64().unwrap_or(0))
+                Ok(value
+                    .as_i64()
+                    .or_else(|| value.as_f64().filter(|_| lenient).map(|f| f as i64))
+                    .unwrap_or(0))
             } else {
                 value
                     .as_str()
@@ -48,7 +130,7 @@ impl TryFrom<RawMetric<'_>> for MetricType {
         };
 
         let parse_f64 = || -> Result<f64, MetricError> {
-            if value.is_f64() {
+            if value.is_f64() || (lenient && value.is_number()) {
                 Ok(value.as_f64().unwrap_or(0.0))
             } else {
                 value
@@ -60,157 +142,72 @@ impl TryFrom<RawMetric<'_>> for MetricType {
             }
         };
 
-        if value.is_boolean() {
-            return Ok(MetricType::Switch(if value.as_bool().unwrap_or(false) {
-                1
-            } else {
-                0
-            }));
-        }
-
-        // Handling cases when value is null or "-" aka not initialized
-        if value.is_null() || value.as_str().map(|v| v == "-").unwrap_or(false) {
-            return Ok(MetricType::Null);
-        }
-
-        // "get.total": "0", INT
-        // "disk.total": "475894423552", BYTES
-        match metric.0 {
-            "indices" | "avail" | "used" | "memory" | "store" | "bytes" => {
-                // /_nodes/stats returns size with size postfix: kb, b, gb
-                // in case parsing to integer fails fallback and try to
-                // parse byte unit
-                return match parse_i64() {
-                    Ok(int) => Ok(MetricType::Bytes(int)),
-                    Err(e) => {
-                        if let Some(byte_str) = value.as_str() {
-                            return Ok(MetricType::Bytes(
-                                // FIX: Possible accuracy loss (Prometheus accepts up to 64 bits)
-                                Byte::from_str(byte_str).map(|b| b.as_u128()).or(Err(e))? as i64,
-                            ));
-                        }
-
-                        Err(e)
-                    }
-                };
-            }
-            // elasticsearch_nodes_stats_fs_io_stats_total_write_kilobytes
-            "kilobytes" => {
-                return match parse_i64() {
-                    Ok(int) => Ok(MetricType::Bytes(int * 1024)),
-                    Err(e) => {
-                        if let Some(byte_str) = value.as_str() {
-                            return Ok(MetricType::Bytes(
-                                // FIX: Possible accuracy loss (Prometheus accepts up to 64 bits)
-                                Byte::from_str(byte_str).map(|b| b.as_u128()).or(Err(e))? as i64
-                                    * 1024,
-                            ));
-                        }
-
-                        Err(e)
+        // In case parsing to integer fails fallback and try to parse byte unit
+        let parse_byte_unit = || -> Result<i64, MetricError> {
+            match parse_i64() {
+                Ok(int) => Ok(int),
+                Err(e) => {
+                    if let Some(byte_str) = value.as_str() {
+                        // FIX: Possible accuracy loss (Prometheus accepts up to 64 bits)
+                        return Ok(Byte::from_str(byte_str).map(|b| b.as_u128()).or(Err(e))? as i64);
                     }
-                };
-            }
-            // Skip these metrics as highly variable or redundant
-            "installed" | "jdk" | "pid" | "date" | "epoch" | "timestamp" | "uptime" => {
-                return Ok(MetricType::Null)
-            }
 
-            "time" | "millis" | "alive" | "timeInQueue" | "frequency" => {
-                return Ok(MetricType::Time(Duration::from_millis(
-                    parse_i64().unwrap_or(0) as u64,
-                )))
-            }
-            _ => {
-                if value.is_number() {
-                    if value.is_i64() {
-                        return Ok(MetricType::Gauge(parse_i64()?));
-                    } else {
-                        return Ok(MetricType::GaugeF(parse_f64()?));
-                    }
+                    Err(e)
                 }
             }
-        }
-
-        // TODO: rethink list matching, label could be matched by default with
-        // attempt to parse number before return as default type label
-        match metric.0 {
-            // timed_out
-            "tripped" | "enabled" | "out" | "value" | "committed" | "searchable" | "compound"
-            | "throttled" => Ok(MetricType::Switch(if value.as_bool().unwrap_or(false) {
-                1
-            } else {
-                0
-            })),
-
-            // Special cases
-            // _cat/health: elasticsearch_cat_health_node_data{cluster="testing"}
-            // _cat/shards: "path.data": "/var/lib/elasticsearch/m1/nodes/0"
-            "data" => match parse_i64() {
-                Ok(number) => Ok(MetricType::Gauge(number)),
-                Err(_) => Ok(MetricType::Label(
-                    value.as_str().ok_or_else(unknown)?.to_owned(),
-                )),
-            },
+        };
 
-            // elasticsearch_cat_thread_pool_size - int
-            // elasticsearch_nodes_stats_indices_query_cache_cache_size should be MetricType::Bytes though
-            // pool_size is an int - MetricType::Gauge
-            "size" => {
-                // parse byte unit
-                match parse_i64() {
-                    Ok(int) => Ok(MetricType::Gauge(int)),
-                    Err(e) => {
-                        if let Some(byte_str) = value.as_str() {
-                            Ok(MetricType::Gauge(
-                                // FIX: Possible accuracy loss (Prometheus accepts up to 64 bits)
-                                Byte::from_str(byte_str).map(|b| b.as_u128()).or(Err(e))? as i64,
-                            ))
-                        } else {
-                            Err(e)
-                        }
-                    }
-                }
+        let metric_type = match rule_type {
+            RuleType::Bytes => MetricType::Bytes(parse_byte_unit()?),
+            RuleType::Kilobytes => MetricType::Bytes(parse_byte_unit()? * 1024),
+            RuleType::Drop => MetricType::Null,
+            RuleType::TimeMs => {
+                MetricType::Time(Duration::from_millis(parse_i64().unwrap_or(0) as u64))
             }
-
-            // docs_per_second -> second - https://www.elastic.co/guide/en/elasticsearch/reference/current/cat-transforms.html
-            "overhead" | "processors" | "primaries" | "min" | "max" | "successful" | "nodes"
-            | "fetch" | "order" | "largest" | "rejected" | "completed" | "queue" | "active"
-            | "core" | "tasks" | "relo" | "unassign" | "init" | "files" | "ops" | "recovered"
-            | "generation" | "contexts" | "listeners" | "pri" | "rep" | "docs" | "count"
-            | "compilations" | "deleted" | "shards" | "checkpoint" | "cpu" | "triggered"
-            | "evictions" | "failed" | "total" | "current" | "operations" | "insertOrder"
-            | "processed" | "indexed" | "second" | "failure" => Ok(MetricType::Gauge(parse_i64()?)),
-
-            "avg" | "1m" | "5m" | "15m" | "number" | "percent" | "progress" => {
-                Ok(MetricType::GaugeF(parse_f64()?))
+            RuleType::TimeUs => {
+                MetricType::Time(Duration::from_micros(parse_i64().unwrap_or(0) as u64))
             }
-
-            "types" | "usage" | "mount" | "group" | "rank" | "path" | "roles" | "context"
-            | "cluster" | "repository" | "snapshot" | "stage" | "uuid" | "component" | "master"
-            | "role" | "alias" | "filter" | "search" | "flavor" | "string" | "address"
-            | "health" | "build" | "node" | "state" | "patterns" | "of" | "segment" | "host"
-            | "ip" | "prirep" | "id" | "status" | "at" | "for" | "details" | "reason" | "port"
-            | "attr" | "field" | "shard" | "index" | "name" | "type" | "version"
-            | "description" | "agent" | "uri" | "priority" | "source" => Ok(MetricType::Label(
-                value.as_str().ok_or_else(unknown)?.to_owned(),
-            )),
-            _ => {
-                if cfg!(debug_assertions) {
-                    println!("Catchall metric: {:?}", metric);
-
-                    let parsed = parse_i64().unwrap_or(-1).to_string();
-
-                    if &parsed != "-1" && parsed.len() == value.as_str().unwrap_or("").len() {
-                        println!("Unhandled metic value {:?}", metric);
-                    }
+            RuleType::TimeNs => {
+                MetricType::Time(Duration::from_nanos(parse_i64().unwrap_or(0) as u64))
+            }
+            RuleType::Number => {
+                if !value.is_number() {
+                    return Ok(None);
                 }
 
-                Ok(MetricType::Label(
-                    value.as_str().ok_or_else(unknown)?.to_owned(),
-                ))
+                if value.is_i64() {
+                    MetricType::Gauge(parse_i64()?)
+                } else {
+                    MetricType::GaugeF(parse_f64()?)
+                }
             }
-        }
+            RuleType::Switch => MetricType::Switch(
+                if value
+                    .as_bool()
+                    .or_else(|| value.as_f64().filter(|_| lenient).map(|n| n != 0.0))
+                    .unwrap_or(false)
+                {
+                    1
+                } else {
+                    0
+                },
+            ),
+            RuleType::Data => match parse_i64() {
+                Ok(number) => MetricType::Gauge(number),
+                Err(_) => MetricType::Label(value.as_str().ok_or_else(unknown)?.to_owned()),
+            },
+            RuleType::Size => MetricType::Gauge(parse_byte_unit()?),
+            RuleType::Gauge => MetricType::Gauge(parse_i64()?),
+            RuleType::Counter => MetricType::Counter(parse_i64()?),
+            RuleType::Float => MetricType::GaugeF(parse_f64()?),
+            RuleType::Label => match value.as_str() {
+                Some(label) => MetricType::Label(label.to_owned()),
+                None if value.is_number() => MetricType::Label(value.to_string()),
+                None => return Err(unknown()),
+            },
+        };
+
+        Ok(Some(metric_type))
     }
 }
 
@@ -238,6 +235,63 @@ mod tests {
         assert_eq!(m.metric_type(), &MetricType::Gauge(2020));
     }
 
+    #[test]
+    fn test_metric_type_rules() {
+        let rules: Vec<MetricRule> = vec![
+            "*_query_cache_cache_size:bytes".parse().unwrap(),
+            "*_requests_count:counter".parse().unwrap(),
+            "~^indices_.*_ratio$:float".parse().unwrap(),
+            "*_uuid:drop".parse().unwrap(),
+        ];
+
+        let value = Value::from("10kB");
+        let m = MetricType::from_rules("indices_query_cache_cache_size", ("size", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::Bytes(10000));
+
+        // Built-in rules are used when user rules don't match
+        let m = MetricType::from_rules("thread_pool_size", ("size", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::Gauge(10000));
+
+        let value = Value::from(12);
+        let m = MetricType::from_rules("transport_requests_count", ("count", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::Counter(12));
+
+        let m = MetricType::from_rules("indices_hit_ratio", ("ratio", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::GaugeF(12.0));
+
+        let value = Value::from("8f9SG");
+        let m = MetricType::from_rules("index_uuid", ("uuid", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::Null);
+    }
+
+    #[test]
+    fn test_metric_type_number_parse() {
+        let rules: Vec<MetricRule> = vec![
+            "*_store_size:bytes".parse().unwrap(),
+            "*_load_avg:float".parse().unwrap(),
+            "*_active:switch".parse().unwrap(),
+        ];
+
+        // Built-in rules: float values of integer types parse as 0
+        let value = Value::from(1.5);
+        let m = MetricType::from_rules("fs_total_used", ("used", &value), &[]);
+        assert_eq!(m.unwrap(), MetricType::Bytes(0));
+        let m = MetricType::from_rules("query_time", ("time", &value), &[]);
+        assert_eq!(m.unwrap(), MetricType::Time(Duration::from_millis(0)));
+
+        // User rules: float values of integer types are truncated
+        let value = Value::from(10.7);
+        let m = MetricType::from_rules("indices_store_size", ("size", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::Bytes(10));
+
+        // User rules: integer values of float and switch types are accepted
+        let value = Value::from(2);
+        let m = MetricType::from_rules("os_load_avg", ("avg", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::GaugeF(2.0));
+        let m = MetricType::from_rules("license_active", ("active", &value), &rules);
+        assert_eq!(m.unwrap(), MetricType::Switch(1));
+    }
+
     #[test]
     fn test_metric_type() {
         let metric = "insertOrder".to_string();
@@ -1,10 +1,18 @@
 use serde_json::Value;
 
+/// Embedded catalog of Elasticsearch field descriptions and units
+pub mod catalog;
+mod histogram;
 mod metric_error;
 mod metric_type;
+mod rules;
 
+pub use histogram::Histogram;
 pub(crate) use metric_error::MetricError;
 pub use metric_type::MetricType;
+pub use rules::{MetricRule, RuleType};
+
+use rules::NumberParse;
 
 pub(crate) type RawMetric<'s> = (&'s str, &'s Value);
 
@@ -16,7 +24,7 @@ pub struct Metric(pub(crate) String, pub(crate) MetricType);
 pub type Metrics = Vec<Metric>;
 
 mod from;
-pub use from::{from_value, from_values};
+pub use from::{from_value, from_values, from_values_with_rules, Flattener};
 
 impl Metric {
     /// Return metric key
@@ -33,13 +41,127 @@ impl Metric {
     pub fn metric_type(&self) -> &MetricType {
         &self.1
     }
+
+    /// Histogram metric from Elasticsearch bucketed array, key is postfixed
+    /// with bucket unit and `_histogram` postfix is dropped
+    pub(crate) fn from_histogram(key: &str, histogram: Histogram) -> Self {
+        let key = key.strip_suffix("_histogram").unwrap_or(key);
+
+        let key = normalize_key(&format!(
+            "{}{}",
+            key.replace(['.', '-', '+'], "_"),
+            histogram.postfix()
+        ));
+
+        Self(key, MetricType::Histogram(histogram))
+    }
+}
+
+/// Time units converted to seconds, replaced only as key postfix as `_ns`
+/// could be a part of other words
+const TIME_POSTFIXES: &[&str] = &["_nanos", "_ns", "_micros"];
+
+/// Elasticsearch byte size units, powers of 1024
+const BYTE_UNITS: &[(&str, &str)] = &[
+    ("b", "b"),
+    ("k", "kb"),
+    ("m", "mb"),
+    ("g", "gb"),
+    ("t", "tb"),
+    ("p", "pb"),
+];
+
+/// Elasticsearch byte size setting value in bytes, e.g.: `500mb`, `500m`,
+/// `1.5GB`, units are powers of 1024 and bare number is bytes, negative
+/// values (e.g.: `-1` disabling setting) are `None`
+///
+/// https://www.elastic.co/guide/en/elasticsearch/reference/current/api-conventions.html#byte-units
+pub(crate) fn byte_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_lowercase();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let exponent = match unit {
+        "" => 0,
+        unit => BYTE_UNITS
+            .iter()
+            .position(|(short, long)| unit == *short || unit == *long)?,
+    };
+
+    let number = number.trim().parse::<f64>().ok()?;
+
+    if !number.is_finite() || number < 0.0 {
+        return None;
+    }
+
+    Some((number * 1024_f64.powi(exponent as i32)) as u64)
+}
+
+/// Metric key of flattened field, e.g.: `shard.id` -> `shard_id`
+pub(crate) fn field_key(field: &str) -> String {
+    normalize_key(&field.replace(['.', '-', '+'], "_"))
+}
+
+fn normalize_key(key: &str) -> String {
+    let key = TIME_POSTFIXES
+        .iter()
+        .find_map(|postfix| key.strip_suffix(postfix))
+        .map(|key| format!("{}_seconds", key))
+        .unwrap_or_else(|| key.to_string());
+
+    let key = key
+        .replace("_kilobytes", "_bytes")
+        .replace("_millis", "_seconds")
+        .replace(' ', "_")
+        .replace(":_", "_") // should come before removing single colon
+        .replace([':', '/', '\\'], "_")
+        .replace(['[', ']'], ":")
+        .to_lowercase();
+
+    debug_assert!(!key.contains(' '), "Key contains space: {}", key);
+
+    key
 }
 
 impl TryFrom<RawMetric<'_>> for Metric {
     type Error = MetricError;
 
     fn try_from(metric: RawMetric) -> Result<Self, MetricError> {
-        let mut key: String = metric.0.replace(['.', '-', '+'], "_");
+        Self::try_from_with_rules(metric, &[])
+    }
+}
+
+impl Metric {
+    /// Parse metric with user metric type rules consulted before built-in rules
+    pub(crate) fn try_from_with_rules(
+        metric: RawMetric,
+        rules: &[MetricRule],
+    ) -> Result<Self, MetricError> {
+        let key = MetricKey::new(metric.0, rules);
+
+        let metric_type =
+            MetricType::from_rule_types((&key.last, metric.1), key.rule_types.into_iter())?;
+
+        Ok(Self(key.key.into(), metric_type))
+    }
+}
+
+/// Metric key parsed once per flattened key
+#[derive(Debug)]
+pub(crate) struct MetricKey {
+    /// Normalized metric key
+    pub(crate) key: Box<str>,
+    /// Last key segment, e.g.: "size" of "query_cache_cache_size"
+    pub(crate) last: Box<str>,
+    /// Types of user and built-in rules matching key in order of precedence
+    pub(crate) rule_types: Vec<(RuleType, NumberParse)>,
+}
+
+impl MetricKey {
+    pub(crate) fn new(raw: &str, rules: &[MetricRule]) -> Self {
+        let key: String = raw.replace(['.', '-', '+'], "_");
 
         let underscore_index = key.rfind('_').unwrap_or(0);
 
@@ -53,20 +175,18 @@ impl TryFrom<RawMetric<'_>> for Metric {
         debug_assert!(!last.contains('.'));
         debug_assert!(!last.contains(' '));
 
-        let metric_type = MetricType::try_from((last, metric.1))?;
-
-        key = key
-            .replace("_kilobytes", "_bytes")
-            .replace("_millis", "_seconds")
-            .replace(' ', "_")
-            .replace(":_", "_") // should come before removing single colon
-            .replace([':', '/', '\\'], "_")
-            .replace(['[', ']'], ":")
-            .to_lowercase();
-
-        debug_assert!(!key.contains(' '), "Key contains space: {}", key);
-
-        Ok(Self(key, metric_type))
+        let rule_types = rules
+            .iter()
+            .chain(rules::DEFAULT_RULES.iter())
+            .filter(|rule| rule.is_match(&key, last))
+            .map(|rule| (rule.rule_type(), rule.number_parse()))
+            .collect();
+
+        Self {
+            key: normalize_key(&key).into(),
+            last: last.into(),
+            rule_types,
+        }
     }
 }
 
@@ -74,6 +194,32 @@ impl TryFrom<RawMetric<'_>> for Metric {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_byte_size() {
+        const KB: u64 = 1024;
+
+        for (value, expected) in [
+            ("100", Some(100)),
+            ("100b", Some(100)),
+            ("10k", Some(10 * KB)),
+            ("10kb", Some(10 * KB)),
+            ("500m", Some(500 * KB * KB)),
+            ("500mb", Some(500 * KB * KB)),
+            ("1.5g", Some(3 * KB * KB * KB / 2)),
+            ("1.5GB", Some(3 * KB * KB * KB / 2)),
+            ("2t", Some(2 * KB.pow(4))),
+            ("2tb", Some(2 * KB.pow(4))),
+            ("1p", Some(KB.pow(5))),
+            ("1pb", Some(KB.pow(5))),
+            (" 40 mb ", Some(40 * KB * KB)),
+            ("-1", None),
+            ("10xb", None),
+            ("mb", None),
+        ] {
+            assert_eq!(byte_size(value), expected, "{}", value);
+        }
+    }
+
     #[test]
     fn test_try_from_raw_metric() {
         use std::time::Duration;
@@ -99,6 +245,36 @@ mod tests {
             &MetricType::Time(Duration::from_millis(10))
         );
 
+        let metric = "adaptive_selection_avg_response_time_ns".to_string();
+        let raw: RawMetric = (&metric, &Value::from(196669342));
+
+        let m = Metric::try_from(raw).unwrap();
+        assert_eq!(&m.key(), &"adaptive_selection_avg_response_time_seconds");
+        assert_eq!(
+            m.metric_type(),
+            &MetricType::Time(Duration::from_nanos(196669342))
+        );
+
+        let metric = "search_query_time_in_nanos".to_string();
+        let raw: RawMetric = (&metric, &Value::from(2000));
+
+        let m = Metric::try_from(raw).unwrap();
+        assert_eq!(&m.key(), &"search_query_time_in_seconds");
+        assert_eq!(
+            m.metric_type(),
+            &MetricType::Time(Duration::from_nanos(2000))
+        );
+
+        let metric = "gc_pause_micros".to_string();
+        let raw: RawMetric = (&metric, &Value::from(1500));
+
+        let m = Metric::try_from(raw).unwrap();
+        assert_eq!(&m.key(), &"gc_pause_seconds");
+        assert_eq!(
+            m.metric_type(),
+            &MetricType::Time(Duration::from_micros(1500))
+        );
+
         let metric = "thread_pool_security-crypto_queue_size".to_string();
         let raw: RawMetric = (&metric, &Value::from("1000"));
 
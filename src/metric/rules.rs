@@ -0,0 +1,405 @@
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+use super::MetricError;
+
+/// Metric type assigned to matched metric key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleType {
+    /// Bytes, byte unit strings such as "10kb" are parsed
+    Bytes,
+    /// Kilobytes converted to bytes
+    Kilobytes,
+    /// Integer gauge
+    Gauge,
+    /// Float gauge
+    Float,
+    /// Time in milliseconds converted to seconds
+    TimeMs,
+    /// Time in microseconds converted to seconds
+    TimeUs,
+    /// Time in nanoseconds converted to seconds
+    TimeNs,
+    /// Switch of true/false
+    Switch,
+    /// Label
+    Label,
+    /// Drop metric
+    Drop,
+    /// Monotonic counter
+    Counter,
+
+    // Built-in rules only
+    /// Any JSON number is either integer or float gauge
+    Number,
+    /// Integer gauge, otherwise label, e.g.: _cat/shards "path.data"
+    Data,
+    /// Integer gauge, byte unit strings such as "10kb" are parsed
+    Size,
+}
+
+impl FromStr for RuleType {
+    type Err = MetricError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(match input {
+            "bytes" => RuleType::Bytes,
+            "kilobytes" => RuleType::Kilobytes,
+            "gauge" => RuleType::Gauge,
+            "float" => RuleType::Float,
+            "time_ms" => RuleType::TimeMs,
+            "time_us" => RuleType::TimeUs,
+            "time_ns" => RuleType::TimeNs,
+            "switch" => RuleType::Switch,
+            "label" => RuleType::Label,
+            "drop" => RuleType::Drop,
+            "counter" => RuleType::Counter,
+            _ => {
+                return Err(MetricError::unknown(
+                    format!(
+                        "unknown metric type `{}`, expected one of: bytes|kilobytes|gauge|float|time_ms|time_us|time_ns|switch|label|drop|counter",
+                        input
+                    ),
+                    None,
+                ))
+            }
+        })
+    }
+}
+
+/// Number parsing of matched rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NumberParse {
+    /// Built-in rules: float values of integer types parse as 0, integer
+    /// values are not accepted by float types
+    Strict,
+    /// User rules: float values of integer types are truncated, integer
+    /// values of float and switch types are accepted
+    Lenient,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// Last key segment is one of, e.g.: "size" in "query_cache_cache_size"
+    Segment(&'static [&'static str]),
+    /// Any key
+    Any,
+    /// Glob or regex on full key
+    Pattern(Regex),
+}
+
+/// Metric type rule, matching either by glob or by regex (prefixed with `~`) on
+/// full metric key having `.`, `-`, `+` replaced with `_`, e.g.:
+///
+/// - `*_query_cache_cache_size:bytes`
+/// - `~^indices_.*_ratio$:float`
+#[derive(Debug, Clone)]
+pub struct MetricRule {
+    matcher: Matcher,
+    rule_type: RuleType,
+    source: String,
+}
+
+impl MetricRule {
+    const fn segment(segments: &'static [&'static str], rule_type: RuleType) -> Self {
+        Self {
+            matcher: Matcher::Segment(segments),
+            rule_type,
+            source: String::new(),
+        }
+    }
+
+    /// Rule metric type
+    pub fn rule_type(&self) -> RuleType {
+        self.rule_type
+    }
+
+    /// Number parsing of rule, user rules are lenient
+    pub(crate) fn number_parse(&self) -> NumberParse {
+        match self.matcher {
+            Matcher::Pattern(_) => NumberParse::Lenient,
+            _ => NumberParse::Strict,
+        }
+    }
+
+    /// Whether rule matches full metric key or it's last segment
+    pub fn is_match(&self, key: &str, last: &str) -> bool {
+        match self.matcher {
+            Matcher::Segment(segments) => segments.contains(&last),
+            Matcher::Any => true,
+            Matcher::Pattern(ref regex) => regex.is_match(key),
+        }
+    }
+}
+
+/// Rules are equal by their source, built-in rules by matched segments
+impl PartialEq for MetricRule {
+    fn eq(&self, other: &Self) -> bool {
+        let matcher = match (&self.matcher, &other.matcher) {
+            (Matcher::Segment(a), Matcher::Segment(b)) => a == b,
+            (Matcher::Any, Matcher::Any) => true,
+            (Matcher::Pattern(_), Matcher::Pattern(_)) => self.source == other.source,
+            _ => false,
+        };
+
+        matcher && self.rule_type == other.rule_type
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut output = String::with_capacity(glob.len() + 8);
+    output.push('^');
+
+    for c in glob.chars() {
+        match c {
+            '*' => output.push_str(".*"),
+            '?' => output.push('.'),
+            c => output.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    output.push('$');
+    output
+}
+
+impl FromStr for MetricRule {
+    type Err = MetricError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        // Metric keys may contain colons, type is always last
+        let (pattern, rule_type) = input.rsplit_once(':').ok_or_else(|| {
+            MetricError::unknown(
+                format!("rule `{}` must be of format `PATTERN:TYPE`", input),
+                None,
+            )
+        })?;
+
+        let regex = match pattern.strip_prefix('~') {
+            Some(regex) => regex.to_string(),
+            None => glob_to_regex(pattern),
+        };
+
+        Ok(Self {
+            matcher: Matcher::Pattern(Regex::new(&regex).map_err(|e| {
+                MetricError::unknown(format!("rule `{}` invalid pattern {}", input, e), None)
+            })?),
+            rule_type: rule_type.parse()?,
+            source: input.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for MetricRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.matcher {
+            Matcher::Pattern(_) => write!(f, "{}", self.source),
+            Matcher::Segment(segments) => write!(f, "{}:{:?}", segments.join("|"), self.rule_type),
+            Matcher::Any => write!(f, "*:{:?}", self.rule_type),
+        }
+    }
+}
+
+/// Built-in rules matched by last key segment, consulted after user rules in order
+///
+/// "get.total": "0", INT
+/// "disk.total": "475894423552", BYTES
+pub(crate) const DEFAULT_RULES: &[MetricRule] = &[
+    // /_nodes/stats returns size with size postfix: kb, b, gb
+    // in case parsing to integer fails fallback and try to
+    // parse byte unit
+    MetricRule::segment(
+        &["indices", "avail", "used", "memory", "store", "bytes"],
+        RuleType::Bytes,
+    ),
+    // elasticsearch_nodes_stats_fs_io_stats_total_write_kilobytes
+    MetricRule::segment(&["kilobytes"], RuleType::Kilobytes),
+    // Skip these metrics as highly variable or redundant
+    MetricRule::segment(
+        &[
+            "installed",
+            "jdk",
+            "pid",
+            "date",
+            "epoch",
+            "timestamp",
+            "uptime",
+        ],
+        RuleType::Drop,
+    ),
+    MetricRule::segment(
+        &["time", "millis", "alive", "timeInQueue", "frequency"],
+        RuleType::TimeMs,
+    ),
+    // adaptive_selection: "avg_response_time_ns": 196669342
+    // "total_time_in_nanos": 2000
+    MetricRule::segment(&["nanos", "ns"], RuleType::TimeNs),
+    MetricRule::segment(&["micros"], RuleType::TimeUs),
+    MetricRule {
+        matcher: Matcher::Any,
+        rule_type: RuleType::Number,
+        source: String::new(),
+    },
+    // TODO: rethink list matching, label could be matched by default with
+    // attempt to parse number before return as default type label
+    // timed_out
+    MetricRule::segment(
+        &[
+            "tripped",
+            "enabled",
+            "out",
+            "value",
+            "committed",
+            "searchable",
+            "compound",
+            "throttled",
+        ],
+        RuleType::Switch,
+    ),
+    // Special cases
+    // _cat/health: elasticsearch_cat_health_node_data{cluster="testing"}
+    // _cat/shards: "path.data": "/var/lib/elasticsearch/m1/nodes/0"
+    MetricRule::segment(&["data"], RuleType::Data),
+    // elasticsearch_cat_thread_pool_size - int
+    // elasticsearch_nodes_stats_indices_query_cache_cache_size - int, number of cached entries
+    // pool_size is an int - MetricType::Gauge
+    MetricRule::segment(&["size"], RuleType::Size),
+    // docs_per_second -> second - https://www.elastic.co/guide/en/elasticsearch/reference/current/cat-transforms.html
+    MetricRule::segment(
+        &[
+            "overhead",
+            "processors",
+            "primaries",
+            "min",
+            "max",
+            "successful",
+            "nodes",
+            "fetch",
+            "order",
+            "largest",
+            "rejected",
+            "completed",
+            "queue",
+            "active",
+            "core",
+            "tasks",
+            "relo",
+            "unassign",
+            "init",
+            "files",
+            "ops",
+            "recovered",
+            "generation",
+            "contexts",
+            "listeners",
+            "pri",
+            "rep",
+            "docs",
+            "count",
+            "compilations",
+            "deleted",
+            "shards",
+            "checkpoint",
+            "cpu",
+            "triggered",
+            "evictions",
+            "failed",
+            "total",
+            "current",
+            "operations",
+            "insertOrder",
+            "processed",
+            "indexed",
+            "second",
+            "failure",
+        ],
+        RuleType::Gauge,
+    ),
+    MetricRule::segment(
+        &["avg", "1m", "5m", "15m", "number", "percent", "progress"],
+        RuleType::Float,
+    ),
+    MetricRule::segment(
+        &[
+            "types",
+            "usage",
+            "mount",
+            "group",
+            "rank",
+            "path",
+            "roles",
+            "context",
+            "cluster",
+            "repository",
+            "snapshot",
+            "stage",
+            "uuid",
+            "component",
+            "master",
+            "role",
+            "alias",
+            "filter",
+            "search",
+            "flavor",
+            "string",
+            "address",
+            "health",
+            "build",
+            "node",
+            "state",
+            "patterns",
+            "of",
+            "segment",
+            "host",
+            "ip",
+            "prirep",
+            "id",
+            "status",
+            "at",
+            "for",
+            "details",
+            "reason",
+            "port",
+            "attr",
+            "field",
+            "shard",
+            "index",
+            "name",
+            "type",
+            "version",
+            "description",
+            "agent",
+            "uri",
+            "priority",
+            "source",
+        ],
+        RuleType::Label,
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_rule_from_str() {
+        let rule: MetricRule = "*_query_cache_cache_size:bytes".parse().unwrap();
+        assert_eq!(rule.rule_type(), RuleType::Bytes);
+        assert!(rule.is_match("indices_query_cache_cache_size", "size"));
+        assert!(!rule.is_match("thread_pool_size", "size"));
+        assert_eq!(rule.to_string(), "*_query_cache_cache_size:bytes");
+
+        // Colons are allowed in the pattern
+        let rule: MetricRule = "transport_actions_*info:n:_*:counter".parse().unwrap();
+        assert_eq!(rule.rule_type(), RuleType::Counter);
+        assert!(rule.is_match("transport_actions_nodes_info:n:_requests_count", "count"));
+
+        let rule: MetricRule = "~^jvm_.*_(used|max)_percent$:float".parse().unwrap();
+        assert_eq!(rule.rule_type(), RuleType::Float);
+        assert!(rule.is_match("jvm_mem_heap_used_percent", "percent"));
+
+        assert!("no_type".parse::<MetricRule>().is_err());
+        assert!("*_size:nope".parse::<MetricRule>().is_err());
+        assert!("~(:gauge".parse::<MetricRule>().is_err());
+    }
+}
@@ -5,7 +5,7 @@ use super::responses::CatResponse;
 
 pub(crate) const SUBSYSTEM: &str = "cat_aliases";
 
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let response = exporter
         .client()
         .cat()
@@ -23,7 +23,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
         .await?
         .into_values(inject_cat_aliases_info);
 
-    Ok(metric::from_values(values))
+    Ok(values)
 }
 
 fn inject_cat_aliases_info(map: &mut SerdeMap<String, Value>) {
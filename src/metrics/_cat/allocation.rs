@@ -3,7 +3,7 @@ use elasticsearch::params::Bytes;
 
 pub(crate) const SUBSYSTEM: &str = "cat_allocation";
 
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let response = exporter
         .client()
         .cat()
@@ -17,7 +17,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
         .send()
         .await?;
 
-    Ok(metric::from_values(response.json::<Vec<Value>>().await?))
+    response.json::<Vec<Value>>().await
 }
 
 crate::poll_metrics!();
@@ -2,7 +2,7 @@ use elasticsearch::params::Time;
 
 pub(crate) const SUBSYSTEM: &str = "cat_health";
 
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let response = exporter
         .client()
         .cat()
@@ -14,7 +14,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
         .send()
         .await?;
 
-    Ok(metric::from_values(response.json::<Vec<Value>>().await?))
+    response.json::<Vec<Value>>().await
 }
 
 crate::poll_metrics!();
@@ -13,6 +13,7 @@ pub(crate) mod recovery;
 pub(crate) mod repositories;
 pub(crate) mod segments;
 pub(crate) mod shards;
+pub(crate) mod shards_summary;
 pub(crate) mod templates;
 pub(crate) mod thread_pool;
 pub(crate) mod transforms;
@@ -5,7 +5,7 @@ use super::responses::CatResponse;
 
 pub(crate) const SUBSYSTEM: &str = "cat_shards";
 
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let filter_path = exporter
         .options()
         .query_filter_path_for_subsystem(SUBSYSTEM);
@@ -36,7 +36,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
         }
     });
 
-    Ok(metric::from_values(values))
+    Ok(values)
 }
 
 crate::poll_metrics!();
@@ -0,0 +1,206 @@
+use elasticsearch::cat::CatShardsParts;
+use elasticsearch::params::Bytes;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+pub(crate) const SUBSYSTEM: &str = "cat_shards_summary";
+
+/// Derived from the same `/_cat/shards` as `cat_shards` narrowed to columns
+/// needed for the summary
+const COLUMNS: &[&str] = &["index", "shard", "prirep", "state", "node", "store"];
+
+/// Node role abbreviations of `/_cat/nodes` holding shards: data, content,
+/// hot, warm, cold and frozen
+const DATA_ROLES: &[char] = &['d', 's', 'h', 'w', 'c', 'f'];
+
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let timeout = exporter.options().timeout_for_subsystem(SUBSYSTEM);
+    let cat = exporter.client().cat();
+
+    let shards = cat
+        .shards(CatShardsParts::Index(&["*"]))
+        .format("json")
+        .h(COLUMNS)
+        .bytes(Bytes::B)
+        .request_timeout(timeout)
+        .send();
+
+    // Data nodes without shards are part of imbalance
+    let nodes = cat
+        .nodes()
+        .format("json")
+        .h(&["name", "node.role"])
+        .request_timeout(timeout)
+        .send();
+
+    let (shards, nodes) = tokio::try_join!(shards, nodes)?;
+    let (shards, nodes) =
+        tokio::try_join!(shards.json::<Vec<Value>>(), nodes.json::<Vec<Value>>())?;
+
+    Ok(summary(
+        &shards,
+        &nodes,
+        exporter.options().exporter_shard_size_threshold,
+    ))
+}
+
+/// Shard store size in bytes, `bytes=b` returns plain integers
+fn store_bytes(shard: &Value) -> Option<u64> {
+    shard["store"].as_str()?.parse().ok()
+}
+
+/// Nearest-rank percentile of sorted sizes
+fn percentile(sorted: &[u64], percentile: f64) -> u64 {
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Max to mean ratio, 1 is perfectly balanced
+fn imbalance_ratio(values: impl Iterator<Item = u64> + Clone) -> f64 {
+    let count = values.clone().count();
+    let max = values.clone().max().unwrap_or(0);
+    let sum: u64 = values.sum();
+
+    if sum == 0 {
+        return 1.0;
+    }
+
+    max as f64 * count as f64 / sum as f64
+}
+
+/// Low cardinality summary of assigned shards:
+/// - per node shard count and store size
+/// - per index shard size min, max, avg and p99
+/// - shards over size threshold and node imbalance ratios, data nodes without
+///   shards count as empty
+fn summary(shards: &[Value], data_nodes: &[Value], threshold: u64) -> Vec<Value> {
+    let mut nodes: BTreeMap<&str, (u64, u64)> = data_nodes
+        .iter()
+        .filter(|node| {
+            node["node.role"]
+                .as_str()
+                .is_some_and(|roles| roles.contains(DATA_ROLES))
+        })
+        .filter_map(|node| node["name"].as_str())
+        .map(|name| (name, (0, 0)))
+        .collect();
+    let mut indices: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    let mut over_threshold = 0;
+
+    for shard in shards.iter() {
+        // Relocating shard is counted on its source node:
+        // "node": "m3-host -> 90.34.9.32 rCsGaKOURrClqJJZVhhsvQ m0-host2"
+        let (Some(index), Some(node), Some(store)) = (
+            shard["index"].as_str(),
+            shard["node"]
+                .as_str()
+                .and_then(|node| node.split(" -> ").next()),
+            store_bytes(shard),
+        ) else {
+            continue;
+        };
+
+        let entry = nodes.entry(node).or_default();
+        entry.0 += 1;
+        entry.1 += store;
+
+        indices.entry(index).or_default().push(store);
+
+        if store > threshold {
+            over_threshold += 1;
+        }
+    }
+
+    let mut values = Vec::with_capacity(nodes.len() + indices.len() + 1);
+
+    values.push(json!({
+        "shards_over_threshold": over_threshold,
+        "shards_threshold_bytes": threshold,
+        "node_shards_imbalance_ratio": imbalance_ratio(nodes.values().map(|node| node.0)),
+        "node_store_imbalance_ratio": imbalance_ratio(nodes.values().map(|node| node.1)),
+    }));
+
+    for (node, (shards, store)) in nodes.iter() {
+        values.push(json!({
+            "node": node,
+            "node_shards": shards,
+            "node_store_bytes": store,
+        }));
+    }
+
+    for (index, mut sizes) in indices.into_iter() {
+        sizes.sort_unstable();
+        let sum: u64 = sizes.iter().sum();
+
+        values.push(json!({
+            "index": index,
+            "index_shards": sizes.len(),
+            "index_shard_min_bytes": sizes[0],
+            "index_shard_max_bytes": sizes[sizes.len() - 1],
+            "index_shard_avg_bytes": sum / sizes.len() as u64,
+            "index_shard_p99_bytes": percentile(&sizes, 99.0),
+        }));
+    }
+
+    values
+}
+
+crate::poll_metrics!();
+
+#[test]
+fn test_cat_shards_summary() {
+    let shards: Vec<Value> = serde_json::from_value(json!([
+        {"index": "logs", "shard": "0", "prirep": "p", "state": "STARTED", "node": "n1", "store": "60000"},
+        {"index": "logs", "shard": "0", "prirep": "r", "state": "STARTED", "node": "n2", "store": "60000"},
+        {"index": "logs", "shard": "1", "prirep": "p", "state": "STARTED", "node": "n1", "store": "20000"},
+        {"index": "logs", "shard": "1", "prirep": "r", "state": "RELOCATING", "node": "n1 -> 10.0.0.3 abc n3", "store": "20000"},
+        {"index": "metrics", "shard": "0", "prirep": "p", "state": "STARTED", "node": "n1", "store": "1000"},
+        {"index": "metrics", "shard": "0", "prirep": "r", "state": "UNASSIGNED", "node": null, "store": null}
+    ]))
+    .unwrap();
+
+    let nodes: Vec<Value> = serde_json::from_value(json!([
+        {"name": "n1", "node.role": "cdfhilmrstw"},
+        {"name": "n2", "node.role": "d"},
+        {"name": "n4", "node.role": "hs"},
+        {"name": "m1", "node.role": "mv"}
+    ]))
+    .unwrap();
+
+    let values = summary(&shards, &nodes, 50000);
+    assert_eq!(values.len(), 6);
+
+    assert_eq!(values[0]["shards_over_threshold"], 2);
+    // n1: 4 shards of 101000 bytes, n2: 1 shard of 60000 bytes, n4: empty
+    // data node, master node m1 is not counted
+    assert_eq!(values[0]["node_shards_imbalance_ratio"], 4.0 * 3.0 / 5.0);
+    assert_eq!(
+        values[0]["node_store_imbalance_ratio"],
+        101000.0 * 3.0 / 161000.0
+    );
+
+    assert_eq!(
+        values[1],
+        json!({"node": "n1", "node_shards": 4, "node_store_bytes": 101000})
+    );
+    assert_eq!(
+        values[3],
+        json!({"node": "n4", "node_shards": 0, "node_store_bytes": 0})
+    );
+    assert_eq!(
+        values[4],
+        json!({
+            "index": "logs",
+            "index_shards": 4,
+            "index_shard_min_bytes": 20000,
+            "index_shard_max_bytes": 60000,
+            "index_shard_avg_bytes": 40000,
+            "index_shard_p99_bytes": 60000,
+        })
+    );
+    assert_eq!(values[5]["index_shard_max_bytes"], 1000);
+
+    // Without data nodes imbalance is of nodes holding shards
+    let values = summary(&shards, &[], 50000);
+    assert_eq!(values[0]["node_shards_imbalance_ratio"], 4.0 * 2.0 / 5.0);
+}
@@ -6,7 +6,7 @@ use elasticsearch::cluster::ClusterHealthParts;
 
 pub(crate) const SUBSYSTEM: &str = "cluster_health";
 
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let response = exporter
         .client()
         .cluster()
@@ -21,7 +21,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
 
     update_health_metrics_from_value(&values, exporter.metrics().cluster_health_status.clone());
 
-    Ok(metric::from_value(values))
+    Ok(vec![values])
 }
 
 const COLORS: [&str; 3] = ["red", "green", "yellow"];
@@ -1,4 +1,6 @@
 mod responses;
 
 pub(crate) mod health;
+pub(crate) mod settings;
 pub(crate) mod stats;
+pub(crate) mod watermarks;
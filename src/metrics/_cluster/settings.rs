@@ -0,0 +1,268 @@
+use fnv::FnvHasher;
+use serde_json::{json, Map};
+use std::hash::Hasher;
+
+use crate::metric::MetricRule;
+
+pub(crate) const SUBSYSTEM: &str = "cluster_settings";
+
+/// Explicitly set settings
+const SOURCES: &[&str] = &["persistent", "transient"];
+
+/// Important settings exported as numbers, enumerations are exported as
+/// position of value, booleans as 0/1 and byte sizes as bytes
+///
+/// Elasticsearch `defaults` only has settings which are not set explicitly,
+/// thus explicitly set value is compared to built-in default of Elasticsearch 8
+const ALLOWLIST: &[(&str, Kind, &str)] = &[
+    (
+        "cluster.routing.allocation.enable",
+        Kind::Enum(&["none", "primaries", "new_primaries", "all"]),
+        "all",
+    ),
+    (
+        "cluster.routing.rebalance.enable",
+        Kind::Enum(&["none", "primaries", "replicas", "all"]),
+        "all",
+    ),
+    (
+        "cluster.routing.allocation.node_concurrent_recoveries",
+        Kind::Number,
+        "2",
+    ),
+    (
+        "cluster.routing.allocation.node_initial_primaries_recoveries",
+        Kind::Number,
+        "4",
+    ),
+    (
+        "cluster.routing.allocation.cluster_concurrent_rebalance",
+        Kind::Number,
+        "2",
+    ),
+    (
+        "cluster.routing.allocation.disk.threshold_enabled",
+        Kind::Bool,
+        "true",
+    ),
+    ("cluster.max_shards_per_node", Kind::Number, "1000"),
+    ("cluster.blocks.read_only", Kind::Bool, "false"),
+    ("cluster.blocks.read_only_allow_delete", Kind::Bool, "false"),
+    ("indices.recovery.max_bytes_per_sec", Kind::Bytes, "40mb"),
+    ("action.destructive_requires_name", Kind::Bool, "true"),
+    ("search.max_buckets", Kind::Number, "65536"),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Number,
+    Bool,
+    Bytes,
+    Enum(&'static [&'static str]),
+}
+
+impl Kind {
+    fn parse(self, value: &str) -> Option<f64> {
+        match self {
+            Self::Number => value.parse().ok(),
+            Self::Bool => value
+                .parse::<bool>()
+                .ok()
+                .map(|value| f64::from(u8::from(value))),
+            Self::Bytes => metric::byte_size(value).map(|bytes| bytes as f64),
+            Self::Enum(values) => values
+                .iter()
+                .position(|known| *known == value)
+                .map(|position| position as f64),
+        }
+    }
+}
+
+/// Setting values of info metric are labels regardless of value, e.g.: "1000"
+pub(crate) fn rules() -> Vec<MetricRule> {
+    ["setting:label", "value:label"]
+        .iter()
+        .filter_map(|rule| rule.parse().ok())
+        .collect()
+}
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/cluster-get-settings.html
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let response = exporter
+        .client()
+        .cluster()
+        .get_settings()
+        .include_defaults(true)
+        .flat_settings(true)
+        .request_timeout(exporter.options().timeout_for_subsystem(SUBSYSTEM))
+        .send()
+        .await?;
+
+    Ok(into_values(&response.json::<Value>().await?))
+}
+
+/// Flat setting value as string, list settings are comma-separated
+fn setting_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(setting_string)
+            .collect::<Vec<String>>()
+            .join(","),
+        value => value.to_string(),
+    }
+}
+
+fn into_values(settings: &Value) -> Vec<Value> {
+    let mut values = Vec::new();
+
+    // Explicitly set settings, transient overriding persistent ones
+    let mut explicit: Map<String, Value> = Map::new();
+
+    for source in SOURCES.iter() {
+        for (setting, value) in settings[source].as_object().into_iter().flatten() {
+            let value = setting_string(value);
+
+            values.push(json!({
+                "setting": setting,
+                "value": value,
+                "source": source,
+                "info": 1,
+            }));
+
+            let _ = explicit.insert(setting.clone(), Value::String(value));
+        }
+    }
+
+    for (setting, kind, default) in ALLOWLIST.iter() {
+        let set = explicit.get(*setting).map(setting_string);
+        let value = set
+            .clone()
+            .or_else(|| settings["defaults"].get(*setting).map(setting_string))
+            .unwrap_or_else(|| default.to_string());
+
+        let Some(number) = kind.parse(&value) else {
+            continue;
+        };
+
+        // Compared as numbers, e.g.: "40mb" equals "41943040b"
+        let non_default = set.is_some() && kind.parse(default) != Some(number);
+
+        values.push(json!({
+            "setting": setting,
+            "setting_value": number,
+            "setting_non_default": u8::from(non_default),
+        }));
+    }
+
+    // Hash of explicitly set settings, serde_json maps are sorted by key
+    let mut hasher = FnvHasher::default();
+    for source in SOURCES.iter() {
+        hasher.write(settings[source].to_string().as_bytes());
+    }
+
+    values.push(json!({
+        "explicit_settings": explicit.len(),
+        // Truncated to be exactly represented as float sample
+        "settings_hash": hasher.finish() as u32,
+    }));
+
+    values
+}
+
+crate::poll_metrics!(rules);
+
+#[test]
+fn test_cluster_settings() {
+    let settings = json!({
+        "persistent": {
+            "cluster.routing.allocation.enable": "all",
+            "cluster.routing.allocation.awareness.attributes": ["zone", "rack"],
+            "indices.recovery.max_bytes_per_sec": "200mb"
+        },
+        "transient": {
+            "cluster.routing.allocation.enable": "none",
+            "search.max_buckets": "65536"
+        },
+        "defaults": {
+            "cluster.routing.rebalance.enable": "all",
+            "cluster.max_shards_per_node": "1000"
+        }
+    });
+
+    let values = into_values(&settings);
+
+    assert!(values.contains(&json!({
+        "setting": "cluster.routing.allocation.awareness.attributes",
+        "value": "zone,rack",
+        "source": "persistent",
+        "info": 1,
+    })));
+    assert!(values.contains(&json!({
+        "setting": "cluster.routing.allocation.enable",
+        "value": "none",
+        "source": "transient",
+        "info": 1,
+    })));
+
+    let allowlisted = |setting: &str| {
+        values
+            .iter()
+            .find(|value| value["setting"] == setting && value.get("setting_value").is_some())
+            .cloned()
+            .unwrap()
+    };
+
+    assert_eq!(
+        allowlisted("cluster.routing.allocation.enable"),
+        json!({"setting": "cluster.routing.allocation.enable", "setting_value": 0.0, "setting_non_default": 1})
+    );
+    assert_eq!(
+        allowlisted("indices.recovery.max_bytes_per_sec")["setting_value"],
+        200.0 * 1024.0 * 1024.0
+    );
+    assert_eq!(
+        allowlisted("indices.recovery.max_bytes_per_sec")["setting_non_default"],
+        1
+    );
+    // Explicitly set to default value
+    assert_eq!(allowlisted("search.max_buckets")["setting_non_default"], 0);
+    // Not set explicitly
+    assert_eq!(
+        allowlisted("cluster.max_shards_per_node")["setting_non_default"],
+        0
+    );
+    // Neither set explicitly nor in defaults, built-in default is exported
+    assert_eq!(
+        allowlisted("cluster.routing.allocation.node_concurrent_recoveries"),
+        json!({
+            "setting": "cluster.routing.allocation.node_concurrent_recoveries",
+            "setting_value": 2.0,
+            "setting_non_default": 0,
+        })
+    );
+
+    let summary = values.last().unwrap();
+    assert_eq!(summary["explicit_settings"], 4);
+
+    // Hash changes with any explicitly set setting
+    let mut changed = settings.clone();
+    changed["transient"]["cluster.routing.allocation.enable"] = json!("all");
+    assert_ne!(
+        into_values(&changed).last().unwrap()["settings_hash"],
+        summary["settings_hash"]
+    );
+
+    let metrics = metric::from_values_with_rules(values.clone(), &rules());
+    assert!(metrics
+        .iter()
+        .flatten()
+        .any(|metric| metric.key() == "value"
+            && *metric.metric_type() == metric::MetricType::Label("zone,rack".into())));
+    assert!(metrics
+        .iter()
+        .flatten()
+        .any(|metric| metric.key() == "setting_value"
+            && *metric.metric_type() == metric::MetricType::GaugeF(0.0)));
+}
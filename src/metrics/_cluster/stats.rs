@@ -2,7 +2,7 @@ use elasticsearch::cluster::ClusterStatsParts;
 
 pub(crate) const SUBSYSTEM: &str = "cluster_stats";
 
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let response = exporter
         .client()
         .cluster()
@@ -11,7 +11,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
         .send()
         .await?;
 
-    Ok(metric::from_value(response.json::<Value>().await?))
+    Ok(vec![response.json::<Value>().await?])
 }
 
 crate::poll_metrics!();
@@ -0,0 +1,218 @@
+use elasticsearch::cat::CatAllocationParts;
+use elasticsearch::params::Bytes;
+use serde_json::{json, Map};
+
+pub(crate) const SUBSYSTEM: &str = "cluster_watermarks";
+
+/// Disk watermark settings prefix
+const WATERMARK: &str = "cluster.routing.allocation.disk.watermark";
+
+/// Watermarks in order of severity
+const WATERMARKS: &[&str] = &["low", "high", "flood_stage"];
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/modules-cluster.html#disk-based-shard-allocation
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let timeout = exporter.options().timeout_for_subsystem(SUBSYSTEM);
+
+    let client = exporter.client();
+    let cluster = client.cluster();
+    let cat = client.cat();
+
+    // Flat settings as `watermark.low` and `watermark.low.max_headroom`
+    // do not fit into nested settings object
+    let settings = cluster
+        .get_settings()
+        .include_defaults(true)
+        .flat_settings(true)
+        .request_timeout(timeout)
+        .send();
+
+    let allocation = cat
+        .allocation(CatAllocationParts::None)
+        .format("json")
+        .h(&["node", "disk.used", "disk.total"])
+        .bytes(Bytes::B)
+        .request_timeout(timeout)
+        .send();
+
+    let (settings, allocation) = tokio::try_join!(settings, allocation)?;
+    let (settings, allocation) =
+        tokio::try_join!(settings.json::<Value>(), allocation.json::<Vec<Value>>())?;
+
+    Ok(into_values(&settings, &allocation))
+}
+
+/// Setting value, transient settings take precedence over persistent ones
+/// which take precedence over defaults
+fn setting<'a>(settings: &'a Value, key: &str) -> Option<&'a str> {
+    ["transient", "persistent", "defaults"]
+        .iter()
+        .find_map(|source| settings[source][key].as_str())
+}
+
+/// Watermark threshold, either used disk ratio (`85%`, `0.85`) or free disk
+/// space in bytes (`500mb`)
+#[derive(Debug, PartialEq)]
+enum Threshold {
+    Ratio(f64),
+    Free(u64),
+}
+
+impl Threshold {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        if let Some(percent) = value.strip_suffix('%') {
+            return percent
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .map(|percent| Self::Ratio(percent / 100.0));
+        }
+
+        match value.parse::<f64>() {
+            Ok(ratio) => Some(Self::Ratio(ratio)),
+            Err(_) => metric::byte_size(value).map(Self::Free),
+        }
+    }
+
+    /// Used bytes of disk at which watermark is reached, ratio watermark
+    /// is capped by max headroom of free space on large disks
+    fn used_bytes(&self, total: u64, max_headroom: Option<u64>) -> u64 {
+        let free = match self {
+            Self::Ratio(ratio) => {
+                let free = (total as f64 * (1.0 - ratio)).round().max(0.0) as u64;
+                max_headroom.map_or(free, |headroom| free.min(headroom))
+            }
+            Self::Free(free) => *free,
+        };
+
+        total.saturating_sub(free)
+    }
+}
+
+fn into_values(settings: &Value, allocation: &[Value]) -> Vec<Value> {
+    let thresholds: Vec<(&str, Threshold, Option<u64>)> = WATERMARKS
+        .iter()
+        .filter_map(|watermark| {
+            let key = format!("{}.{}", WATERMARK, watermark);
+            let threshold = Threshold::parse(setting(settings, &key)?)?;
+            // `-1` disables max headroom
+            let max_headroom =
+                setting(settings, &format!("{}.max_headroom", key)).and_then(metric::byte_size);
+
+            Some((*watermark, threshold, max_headroom))
+        })
+        .collect();
+
+    let mut cluster = Map::new();
+
+    for (watermark, threshold, max_headroom) in thresholds.iter() {
+        match threshold {
+            Threshold::Ratio(ratio) => {
+                let _ = cluster.insert(format!("{}_ratio", watermark), json!(ratio));
+            }
+            Threshold::Free(free) => {
+                let _ = cluster.insert(format!("{}_free_bytes", watermark), json!(free));
+            }
+        }
+
+        if let Some(max_headroom) = max_headroom {
+            let _ = cluster.insert(
+                format!("{}_max_headroom_bytes", watermark),
+                json!(max_headroom),
+            );
+        }
+    }
+
+    let mut values = vec![Value::Object(cluster)];
+
+    for node in allocation.iter() {
+        let disk = |key: &str| {
+            node[key]
+                .as_str()
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+
+        // UNASSIGNED row has no disk
+        let (Some(name), Some(used), Some(total)) =
+            (node["node"].as_str(), disk("disk.used"), disk("disk.total"))
+        else {
+            continue;
+        };
+
+        let mut value = Map::new();
+        let _ = value.insert("node".into(), json!(name));
+
+        for (watermark, threshold, max_headroom) in thresholds.iter() {
+            let threshold = threshold.used_bytes(total, *max_headroom);
+
+            let _ = value.insert(format!("{}_threshold_bytes", watermark), json!(threshold));
+            // Negative once watermark is exceeded
+            let _ = value.insert(
+                format!("{}_remaining_bytes", watermark),
+                json!(threshold as i64 - used as i64),
+            );
+        }
+
+        values.push(Value::Object(value));
+    }
+
+    values
+}
+
+crate::poll_metrics!();
+
+#[test]
+fn test_cluster_watermarks() {
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    assert_eq!(Threshold::parse("85%"), Some(Threshold::Ratio(0.85)));
+    assert_eq!(Threshold::parse("0.9"), Some(Threshold::Ratio(0.9)));
+    assert_eq!(
+        Threshold::parse("500mb"),
+        Some(Threshold::Free(500 * 1024 * 1024))
+    );
+    assert_eq!(
+        Threshold::parse("500m"),
+        Some(Threshold::Free(500 * 1024 * 1024))
+    );
+
+    let settings = json!({
+        "persistent": {"cluster.routing.allocation.disk.watermark.high": "50gb"},
+        "transient": {"cluster.routing.allocation.disk.watermark.low": "80%"},
+        "defaults": {
+            "cluster.routing.allocation.disk.watermark.low": "85%",
+            "cluster.routing.allocation.disk.watermark.low.max_headroom": "-1",
+            "cluster.routing.allocation.disk.watermark.high": "90%",
+            "cluster.routing.allocation.disk.watermark.flood_stage": "95%",
+            "cluster.routing.allocation.disk.watermark.flood_stage.max_headroom": "20gb"
+        }
+    });
+
+    let allocation = vec![
+        json!({"node": "n1", "disk.used": (700 * GB).to_string(), "disk.total": (1000 * GB).to_string()}),
+        json!({"node": "UNASSIGNED", "disk.used": null, "disk.total": null}),
+    ];
+
+    let values = into_values(&settings, &allocation);
+    assert_eq!(values.len(), 2);
+
+    assert_eq!(
+        values[0],
+        json!({
+            "low_ratio": 0.8,
+            "high_free_bytes": 50 * GB,
+            "flood_stage_ratio": 0.95,
+            "flood_stage_max_headroom_bytes": 20 * GB,
+        })
+    );
+
+    assert_eq!(values[1]["node"], "n1");
+    assert_eq!(values[1]["low_threshold_bytes"], 800 * GB);
+    assert_eq!(values[1]["low_remaining_bytes"], 100 * GB);
+    assert_eq!(values[1]["high_threshold_bytes"], 950 * GB);
+    // 5% of 1000GB is capped by 20GB headroom
+    assert_eq!(values[1]["flood_stage_threshold_bytes"], 980 * GB);
+    assert_eq!(values[1]["flood_stage_remaining_bytes"], 280 * GB);
+}
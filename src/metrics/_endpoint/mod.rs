@@ -0,0 +1,40 @@
+use elasticsearch::http::headers::HeaderMap;
+use elasticsearch::http::request::JsonBody;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::endpoint::EndpointConfig;
+use crate::Exporter;
+
+async fn metrics(
+    exporter: &Exporter,
+    endpoint: &EndpointConfig,
+) -> Result<Vec<Value>, elasticsearch::Error> {
+    let response = exporter
+        .client()
+        .send(
+            endpoint.method(),
+            endpoint.path(),
+            HeaderMap::new(),
+            Some(endpoint.params()),
+            endpoint.body().map(|body| JsonBody::new(body.clone())),
+            Some(
+                exporter
+                    .options()
+                    .timeout_for_subsystem(endpoint.subsystem()),
+            ),
+        )
+        .await?
+        .error_for_status_code()?;
+
+    Ok(endpoint.values(response.json::<Value>().await?))
+}
+
+/// Poll user defined REST endpoint as `endpoint_<name>` subsystem
+pub(crate) async fn poll(exporter: Exporter, endpoint: Arc<EndpointConfig>) {
+    crate::metrics::poll(exporter, endpoint.subsystem(), Vec::new(), |exporter| {
+        let endpoint = endpoint.clone();
+        async move { metrics(&exporter, &endpoint).await }
+    })
+    .await
+}
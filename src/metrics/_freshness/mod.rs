@@ -0,0 +1,57 @@
+use elasticsearch::SearchParts;
+use tokio::task::JoinSet;
+
+use crate::freshness::FreshnessConfig;
+pub(crate) use crate::freshness::SUBSYSTEM;
+
+/// Latest document of group, searched on its own so slow or failing group
+/// does not hold back the rest
+async fn latest(
+    exporter: Exporter,
+    config: FreshnessConfig,
+) -> Result<Option<Value>, elasticsearch::Error> {
+    let timeout = config
+        .timeout()
+        .unwrap_or_else(|| exporter.options().timeout_for_subsystem(SUBSYSTEM));
+
+    let response = exporter
+        .client()
+        .search(SearchParts::Index(&config.indices()))
+        .body(config.body())
+        .ignore_unavailable(true)
+        .allow_no_indices(true)
+        .request_timeout(timeout)
+        .send()
+        .await?
+        .error_for_status_code()?;
+
+    let now = chrono::Utc::now().timestamp_millis();
+
+    Ok(config.value(&response.json::<Value>().await?, now))
+}
+
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let mut searches = JoinSet::new();
+
+    for config in exporter.options().exporter_freshness.iter() {
+        let (exporter, config) = (exporter.clone(), config.clone());
+        let _ = searches.spawn(async move {
+            let name = config.name().to_string();
+            (name, latest(exporter, config).await)
+        });
+    }
+
+    let mut values = Vec::new();
+
+    while let Some(result) = searches.join_next().await {
+        match result {
+            Ok((_, Ok(value))) => values.extend(value),
+            Ok((name, Err(e))) => warn!("poll {} group {} err {}", SUBSYSTEM, name, e),
+            Err(e) => error!("poll {} group task err {}", SUBSYSTEM, e),
+        }
+    }
+
+    Ok(values)
+}
+
+crate::poll_metrics!();
@@ -0,0 +1,178 @@
+use elasticsearch::indices::{IndicesGetMappingParts, IndicesGetSettingsParts};
+use serde_json::{json, Map};
+
+pub(crate) const SUBSYSTEM: &str = "index_settings";
+
+/// Index settings of interest, defaults are requested for settings which are
+/// usually not set explicitly
+const SETTINGS_FILTER_PATH: &[&str] = &[
+    "*.settings.index.number_of_shards",
+    "*.settings.index.number_of_replicas",
+    "*.settings.index.blocks",
+    "*.settings.index.refresh_interval",
+    "*.settings.index.mapping.total_fields.limit",
+    "*.settings.index.lifecycle.name",
+    "*.defaults.index.refresh_interval",
+    "*.defaults.index.mapping.total_fields.limit",
+];
+
+const MAPPING_FILTER_PATH: &[&str] = &["*.mappings.properties", "*.mappings.runtime"];
+
+/// Index blocks exported as 0/1, e.g.: index.blocks.read_only_allow_delete
+const BLOCKS: &[&str] = &[
+    "read_only",
+    "read_only_allow_delete",
+    "read",
+    "write",
+    "metadata",
+];
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/indices-get-settings.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/indices-get-mapping.html
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let timeout = exporter.options().timeout_for_subsystem(SUBSYSTEM);
+    let indices = exporter.client().indices();
+
+    let settings = indices
+        .get_settings(IndicesGetSettingsParts::Index(&["_all"]))
+        .include_defaults(true)
+        .filter_path(SETTINGS_FILTER_PATH)
+        .request_timeout(timeout)
+        .send();
+
+    let mapping = indices
+        .get_mapping(IndicesGetMappingParts::None)
+        .filter_path(MAPPING_FILTER_PATH)
+        .request_timeout(timeout)
+        .send();
+
+    let (settings, mapping) = tokio::try_join!(settings, mapping)?;
+    let (settings, mapping) = tokio::try_join!(settings.json::<Value>(), mapping.json::<Value>())?;
+
+    Ok(into_values(settings, &mapping))
+}
+
+/// Index setting, explicitly set or default
+fn setting<'a>(index: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    ["settings", "defaults"].iter().find_map(|source| {
+        path.iter()
+            .try_fold(&index[source]["index"], |value, key| value.get(key))
+    })
+}
+
+fn setting_i64(index: &Value, path: &[&str]) -> Option<i64> {
+    setting(index, path).and_then(|value| match value {
+        Value::String(value) => value.parse().ok(),
+        value => value.as_i64(),
+    })
+}
+
+/// Refresh interval in seconds, `-1` disables refresh
+fn refresh_interval(index: &Value) -> Option<f64> {
+    match setting(index, &["refresh_interval"])?.as_str()? {
+        "-1" => Some(-1.0),
+        interval => interval
+            .parse::<humantime::Duration>()
+            .ok()
+            .map(|interval| interval.as_secs_f64()),
+    }
+}
+
+/// Count of mapped fields as counted by `index.mapping.total_fields.limit`:
+/// object fields, multi-fields and runtime fields included
+fn count_fields(properties: Option<&Map<String, Value>>) -> u64 {
+    properties
+        .into_iter()
+        .flatten()
+        .map(|(_, field)| {
+            1 + count_fields(field["properties"].as_object())
+                + count_fields(field["fields"].as_object())
+        })
+        .sum()
+}
+
+fn into_values(settings: Value, mapping: &Value) -> Vec<Value> {
+    let Value::Object(settings) = settings else {
+        return Vec::new();
+    };
+
+    settings
+        .into_iter()
+        .map(|(name, index)| {
+            let mut value = json!({
+                "index": name,
+                "ilm_policy_attached": u8::from(setting(&index, &["lifecycle", "name"]).is_some()),
+            });
+
+            let mappings = &mapping[&name]["mappings"];
+            value["mapping_fields"] = (count_fields(mappings["properties"].as_object())
+                + count_fields(mappings["runtime"].as_object()))
+            .into();
+
+            for (key, path) in [
+                ("number_of_shards", &["number_of_shards"][..]),
+                ("number_of_replicas", &["number_of_replicas"]),
+                (
+                    "mapping_total_fields_limit",
+                    &["mapping", "total_fields", "limit"],
+                ),
+            ] {
+                if let Some(setting) = setting_i64(&index, path) {
+                    value[key] = setting.into();
+                }
+            }
+
+            if let Some(interval) = refresh_interval(&index) {
+                value["refresh_interval_seconds"] = interval.into();
+            }
+
+            for block in BLOCKS.iter() {
+                let blocked = setting(&index, &["blocks", block])
+                    .map(|value| value == "true" || value == true)
+                    .unwrap_or(false);
+
+                value[format!("blocks_{}", block)] = u8::from(blocked).into();
+            }
+
+            value
+        })
+        .collect()
+}
+
+crate::poll_metrics!();
+
+#[test]
+fn test_index_settings() {
+    let settings: Value =
+        serde_json::from_str(include_str!("../../tests/files/index_settings.json"))
+            .expect("valid json");
+    let mapping: Value = serde_json::from_str(include_str!("../../tests/files/index_mapping.json"))
+        .expect("valid json");
+
+    let values = into_values(settings, &mapping);
+    assert_eq!(values.len(), 2);
+
+    let logs = values
+        .iter()
+        .find(|value| value["index"] == "logs-2023.11.20")
+        .unwrap();
+    assert_eq!(logs["number_of_shards"], 3);
+    assert_eq!(logs["number_of_replicas"], 1);
+    assert_eq!(logs["refresh_interval_seconds"], 30.0);
+    assert_eq!(logs["mapping_fields"], 7);
+    assert_eq!(logs["mapping_total_fields_limit"], 2000);
+    assert_eq!(logs["ilm_policy_attached"], 1);
+    assert_eq!(logs["blocks_read_only_allow_delete"], 0);
+
+    let archive = values
+        .iter()
+        .find(|value| value["index"] == "metrics-archive")
+        .unwrap();
+    assert_eq!(archive["number_of_replicas"], 0);
+    assert_eq!(archive["refresh_interval_seconds"], -1.0);
+    assert_eq!(archive["mapping_fields"], 0);
+    assert_eq!(archive["mapping_total_fields_limit"], 1000);
+    assert_eq!(archive["ilm_policy_attached"], 0);
+    assert_eq!(archive["blocks_read_only_allow_delete"], 1);
+    assert_eq!(archive["blocks_write"], 0);
+}
@@ -0,0 +1,247 @@
+use elasticsearch::nodes::NodesHotThreadsParts;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::metadata::IdToMetadata;
+
+pub(crate) const SUBSYSTEM: &str = "nodes_hot_threads";
+
+/// Busiest threads reported per node
+const THREADS: i64 = 10;
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/cluster-nodes-hot-threads.html
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let response = exporter
+        .client()
+        .nodes()
+        .hot_threads(NodesHotThreadsParts::None)
+        .threads(THREADS)
+        .ignore_idle_threads(true)
+        .request_timeout(exporter.options().timeout_for_subsystem(SUBSYSTEM))
+        .send()
+        .await?;
+
+    let text = response.text().await?;
+
+    Ok(into_values(&text, exporter.nodes_metadata()).await)
+}
+
+/// Busy threads of node grouped by thread pool and top frame class
+#[derive(Debug, Default, PartialEq)]
+struct Busy {
+    threads: u64,
+    cpu_percent: f64,
+}
+
+/// Node ID, thread pool and top stack frame class
+type BusyKey = (String, String, String);
+
+/// Parsed hot threads of all nodes
+#[derive(Debug, Default)]
+struct HotThreads {
+    /// Node names of hot threads headers by node ID
+    names: HashMap<String, String>,
+    busy: BTreeMap<BusyKey, Busy>,
+}
+
+/// Parse plain text `/_nodes/hot_threads` output, e.g.:
+///
+/// ::: {node-1}{U-WnGaTpRxucgde3miiDWw}{...}{10.0.0.1}{10.0.0.1:9300}{d}
+///    93.7% [cpu=93.7%, other=0.0%] (468.5ms out of 500ms) cpu usage by thread 'elasticsearch[node-1][search][T#3]'
+///      6/10 snapshots sharing following 41 elements
+///        app//org.apache.lucene.search.TermScorer.score(TermScorer.java:72)
+///
+/// Top frame of the first snapshot group is taken as what the thread is busy with
+fn parse(text: &str) -> HotThreads {
+    let mut hot_threads = HotThreads::default();
+
+    // Node ID, thread pool and CPU of current thread
+    let mut node: Option<String> = None;
+    let mut thread: Option<(String, f64)> = None;
+    let mut frame_next = false;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix(":::") {
+            let mut fields = header
+                .split('{')
+                .skip(1)
+                .map(|field| field.trim_end().trim_end_matches('}'));
+
+            node = match (fields.next(), fields.next()) {
+                (Some(name), Some(id)) => {
+                    let _ = hot_threads.names.insert(id.into(), name.into());
+                    Some(id.into())
+                }
+                _ => None,
+            };
+            thread = None;
+        } else if let Some((cpu, name)) = thread_line(line) {
+            thread = Some((thread_pool(name), cpu));
+            frame_next = false;
+        } else if line.contains("snapshots sharing following") || line == "unique snapshot" {
+            frame_next = thread.is_some();
+        } else if frame_next && !line.is_empty() {
+            frame_next = false;
+
+            if let (Some(node), Some((pool, cpu))) = (node.as_ref(), thread.take()) {
+                let entry = hot_threads
+                    .busy
+                    .entry((node.clone(), pool, frame_name(line)))
+                    .or_default();
+                entry.threads += 1;
+                entry.cpu_percent += cpu;
+            }
+        }
+    }
+
+    hot_threads
+}
+
+/// CPU percentage and thread name of hot thread line, e.g.:
+/// 93.7% [cpu=93.7%, other=0.0%] (468.5ms out of 500ms) cpu usage by thread 'elasticsearch[node-1][search][T#3]'
+fn thread_line(line: &str) -> Option<(f64, &str)> {
+    let (cpu, rest) = line.split_once("% ")?;
+    let cpu = cpu.parse::<f64>().ok()?;
+
+    let (_, name) = rest.split_once(" by thread '")?;
+
+    Some((cpu, name.trim_end_matches('\'')))
+}
+
+/// Thread pool of Elasticsearch thread name, e.g.: elasticsearch[node-1][search][T#3],
+/// merges are named by index and shard: elasticsearch[node-1][[logs][0]: Lucene Merge Thread #12]
+fn thread_pool(name: &str) -> String {
+    let pool = name
+        .strip_prefix("elasticsearch[")
+        .and_then(|rest| rest.split_once("]["))
+        .map(|(_, rest)| rest);
+
+    match pool {
+        Some(pool) if pool.starts_with('[') && pool.contains("Merge Thread") => "merge".into(),
+        Some(pool) if !pool.starts_with('[') => pool
+            .split(']')
+            .next()
+            .unwrap_or_default()
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        _ => "other".into(),
+    }
+}
+
+/// Class of stack frame without module, method and source location, e.g.:
+/// app//org.apache.lucene.search.TermScorer.score(TermScorer.java:72) -> org.apache.lucene.search.TermScorer
+///
+/// Frame is exported as label, thus it is cut down to class name to keep
+/// label values bounded, nested and generated classes (e.g.: lambdas
+/// `Foo$$Lambda/0x...`) are exported as their outer class
+fn frame_name(line: &str) -> String {
+    let frame = line.split('(').next().unwrap_or(line);
+    let class = frame.rsplit_once('.').map_or(frame, |(class, _)| class);
+    let class = class.split('$').next().unwrap_or(class);
+
+    class.rsplit('/').next().unwrap_or(class).to_string()
+}
+
+async fn into_values(text: &str, metadata: &IdToMetadata) -> Vec<Value> {
+    let metadata = metadata.read().await;
+
+    let HotThreads { names, busy } = parse(text);
+
+    busy.into_iter()
+        .map(|((node, thread_pool, frame), busy)| {
+            // Header node name in case node metadata is not refreshed yet
+            let name = metadata
+                .get(&node)
+                .map(|data| data.name.as_str())
+                .or_else(|| names.get(&node).map(String::as_str))
+                .unwrap_or(&node);
+
+            serde_json::json!({
+                "name": name,
+                "thread_pool": thread_pool,
+                "frame": frame,
+                "busy_threads": busy.threads,
+                "cpu_percent": busy.cpu_percent,
+            })
+        })
+        .collect()
+}
+
+crate::poll_metrics!();
+
+#[tokio::test]
+async fn test_nodes_hot_threads() {
+    use tokio::sync::RwLock;
+
+    use crate::metadata::node_data::{NodeData, NodeDataMap};
+
+    let text = include_str!("../../tests/files/nodes_hot_threads.txt");
+
+    let HotThreads { names, busy } = parse(text);
+    assert_eq!(names["U-WnGaTpRxucgde3miiDWw"], "m1-nodename.example.com");
+    assert_eq!(busy.len(), 4);
+    assert_eq!(
+        busy[&(
+            "U-WnGaTpRxucgde3miiDWw".into(),
+            "search".into(),
+            "org.apache.lucene.search.TermScorer".into()
+        )],
+        Busy {
+            threads: 2,
+            cpu_percent: 93.7 + 88.2
+        }
+    );
+    assert!(busy.contains_key(&(
+        "U-WnGaTpRxucgde3miiDWw".into(),
+        "merge".into(),
+        "org.apache.lucene.codecs.lucene90.Lucene90DocValuesConsumer".into()
+    )));
+    assert!(busy.contains_key(&(
+        "Bz3KGDH8QJqsyYi0jqF2Ww".into(),
+        "other".into(),
+        "java.lang.ProcessHandleImpl".into()
+    )));
+
+    let mut metadata = NodeDataMap::new();
+    let _ = metadata.insert(
+        "Bz3KGDH8QJqsyYi0jqF2Ww".into(),
+        NodeData {
+            name: "d2".into(),
+            ..Default::default()
+        },
+    );
+    let metadata = RwLock::new(metadata);
+
+    let values = into_values(text, &metadata).await;
+    assert!(values
+        .iter()
+        .any(|value| value["name"] == "m1-nodename.example.com"));
+    assert!(values.contains(&serde_json::json!({
+        "name": "d2",
+        "thread_pool": "transport_worker",
+        "frame": "sun.nio.ch.EPoll",
+        "busy_threads": 1,
+        "cpu_percent": 12.5,
+    })));
+}
+
+#[test]
+fn test_hot_threads_frame_name() {
+    assert_eq!(
+        frame_name("app//org.apache.lucene.search.TermScorer.score(TermScorer.java:72)"),
+        "org.apache.lucene.search.TermScorer"
+    );
+    assert_eq!(
+        frame_name("java.base@21/java.lang.Thread.run(Thread.java:1583)"),
+        "java.lang.Thread"
+    );
+    assert_eq!(
+        frame_name("app//org.elasticsearch.Foo$$Lambda/0x00007f4c1c4a2b58.run(Unknown Source)"),
+        "org.elasticsearch.Foo"
+    );
+    assert_eq!(
+        frame_name("org.elasticsearch.Foo$Inner.bar(Foo.java:1)"),
+        "org.elasticsearch.Foo"
+    );
+}
@@ -5,7 +5,7 @@ use super::responses::NodesResponse;
 pub(crate) const SUBSYSTEM: &str = "nodes_info";
 
 // https://www.elastic.co/guide/en/elasticsearch/reference/current/cluster-nodes-stats.html
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let response = exporter
         .client()
         .nodes()
@@ -22,7 +22,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
         .into_values(exporter.nodes_metadata(), REMOVE_KEYS)
         .await;
 
-    Ok(metric::from_values(values))
+    Ok(values)
 }
 
 const REMOVE_KEYS: &[&str] = &[
@@ -1,5 +1,6 @@
 mod responses;
 
+pub(crate) mod hot_threads;
 pub(crate) mod info;
 pub(crate) mod stats;
 pub(crate) mod usage;
@@ -1,11 +1,15 @@
 use elasticsearch::nodes::NodesStatsParts;
 
+use serde_json::Map;
+
 use super::responses::NodesResponse;
+use crate::metadata::IdToMetadata;
+use crate::metric::MetricRule;
 
 pub(crate) const SUBSYSTEM: &str = "nodes_stats";
 
 // https://www.elastic.co/guide/en/elasticsearch/reference/current/cluster-nodes-stats.html
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let fields = exporter.options().query_fields_for_subsystem(SUBSYSTEM);
     let path_params = exporter.options().path_parameters_for_subsystem(SUBSYSTEM);
 
@@ -21,35 +25,209 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
 
     let response = nodes_stats.send().await?;
 
-    let values = response
+    let mut values = response
         .json::<NodesResponse>()
         .await?
         .into_values(exporter.nodes_metadata(), REMOVE_KEYS)
         .await;
 
-    Ok(metric::from_values(values))
+    adaptive_selection(&mut values, exporter.nodes_metadata()).await;
+
+    let mut labeled = Vec::new();
+
+    for value in values.iter_mut() {
+        labeled.extend(breakers(value));
+        labeled.extend(indexing_pressure(value));
+    }
+
+    values.extend(labeled);
+
+    Ok(values)
+}
+
+/// Breaker trips and indexing pressure rejections only ever increase,
+/// breaker overhead is reported either as integer or float, e.g.: 1 or 1.03
+pub(crate) fn rules() -> Vec<MetricRule> {
+    [
+        "breakers_overhead:float",
+        "breakers_tripped:counter",
+        "indexing_pressure_rejections:counter",
+    ]
+    .iter()
+    .filter_map(|rule| rule.parse().ok())
+    .collect()
+}
+
+/// Node labels injected into every nested object (name, ip, etc.)
+fn injected_labels(map: &Map<String, Value>) -> Map<String, Value> {
+    map.iter()
+        .filter(|(_, value)| value.is_string())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Circuit breakers are keyed by breaker name, e.g.:
+/// "breakers": {"parent": {"limit_size_in_bytes": 31653573427, "limit_size": "29.4gb", "overhead": 1, "tripped": 0}}
+///
+/// Take these out of node stats into values of their own labeled by breaker
+/// name to avoid breaker name ending up in metric key, top level `breaker`
+/// label isn't prefixed, human readable sizes are dropped
+fn breakers(value: &mut Value) -> Vec<Value> {
+    let Some(Value::Object(named)) = value.as_object_mut().and_then(|map| map.remove("breakers"))
+    else {
+        return Vec::new();
+    };
+
+    let labels = injected_labels(&named);
+
+    named
+        .into_iter()
+        .filter(|(_, breaker)| breaker.is_object())
+        .map(|(name, breaker)| {
+            let mut map = labels.clone();
+            let _ = map.insert("breaker".into(), Value::String(name));
+
+            for (key, from) in [
+                ("breakers_limit_size_bytes", "limit_size_in_bytes"),
+                ("breakers_estimated_size_bytes", "estimated_size_in_bytes"),
+                ("breakers_overhead", "overhead"),
+                ("breakers_tripped", "tripped"),
+            ] {
+                if let Some(metric) = breaker.get(from) {
+                    let _ = map.insert(key.into(), metric.clone());
+                }
+            }
+
+            Value::Object(map)
+        })
+        .collect()
+}
+
+/// Indexing pressure stages exported with `stage` label
+const INDEXING_PRESSURE_STAGES: &[&str] = &["coordinating", "primary", "replica"];
+
+/// Indexing pressure memory is keyed by stage, e.g.:
+/// "indexing_pressure": {"memory": {"current": {"primary_in_bytes": 0}, "total": {"primary_in_bytes": 835347051791, "primary_rejections": 0}}}
+///
+/// Take these out of node stats into values of their own labeled by stage,
+/// memory limit is kept per node
+fn indexing_pressure(value: &mut Value) -> Vec<Value> {
+    let Some(Value::Object(pressure)) = value
+        .as_object_mut()
+        .and_then(|map| map.remove("indexing_pressure"))
+    else {
+        return Vec::new();
+    };
+
+    let labels = injected_labels(&pressure);
+    let Some(memory) = pressure.get("memory").filter(|memory| memory.is_object()) else {
+        return Vec::new();
+    };
+
+    if let (Some(node), Some(limit)) = (value.as_object_mut(), memory.get("limit_in_bytes")) {
+        let _ = node.insert("indexing_pressure_memory_limit_bytes".into(), limit.clone());
+    }
+
+    INDEXING_PRESSURE_STAGES
+        .iter()
+        .map(|stage| {
+            let mut map = labels.clone();
+            let _ = map.insert("stage".into(), Value::from(*stage));
+
+            for (key, from, metric) in [
+                (
+                    "indexing_pressure_current_bytes",
+                    "current",
+                    format!("{}_in_bytes", stage),
+                ),
+                (
+                    "indexing_pressure_total_bytes",
+                    "total",
+                    format!("{}_in_bytes", stage),
+                ),
+                (
+                    "indexing_pressure_rejections",
+                    "total",
+                    format!("{}_rejections", stage),
+                ),
+            ] {
+                if let Some(metric) = memory[from].get(&metric) {
+                    let _ = map.insert(key.into(), metric.clone());
+                }
+            }
+
+            Value::Object(map)
+        })
+        .collect()
+}
+
+/// Adaptive replica selection statistics are keyed by target node ID, e.g.:
+/// "adaptive_selection": {"U-WnGaTpRxucgde3miiDWw": {"avg_response_time_ns": 196669342, "rank": "196.7"}}
+///
+/// Convert these to array of node pairs labeled by target node name
+/// to avoid node ID ending up in metric key
+async fn adaptive_selection(values: &mut [Value], metadata: &IdToMetadata) {
+    let metadata_read = metadata.read().await;
+
+    for value in values.iter_mut() {
+        let Some(selection) = value
+            .as_object_mut()
+            .and_then(|map| map.get_mut("adaptive_selection"))
+        else {
+            continue;
+        };
+
+        let Some(targets) = selection.as_object_mut() else {
+            continue;
+        };
+
+        let mut pairs: Vec<Value> = Vec::with_capacity(targets.len());
+
+        for (node_id, mut target) in std::mem::take(targets).into_iter() {
+            let Some(map) = target.as_object_mut() else {
+                continue;
+            };
+
+            let target_node = metadata_read
+                .get(&node_id)
+                .map(|node| node.name.to_string())
+                .unwrap_or(node_id);
+            let _ = map.insert("target_node".into(), Value::String(target_node));
+
+            // Rank is reported as string, e.g.: "196.7"
+            if let Some(rank) = map
+                .get("rank")
+                .and_then(Value::as_str)
+                .and_then(|rank| rank.parse::<f64>().ok())
+            {
+                let _ = map.insert("rank".into(), Value::from(rank));
+            }
+
+            pairs.push(target);
+        }
+
+        *selection = Value::Array(pairs);
+    }
 }
 
-// NOTE:
-// enabling adaptive_selection exposes metrics in nanoseconds, e.g.: "avg_response_time_ns": 196669342
 const REMOVE_KEYS: &[&str] = &[
     "timestamp",
     "attributes",
     "cgroup",
-    "adaptive_selection",
     "mapped - 'non-volatile memory'",
     "pipelines",
     "classes",
     "script",
 ];
 
-crate::poll_metrics!();
+crate::poll_metrics!(rules);
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use crate::{
+        collection::Collection,
         metadata::node_data::{NodeData, NodeDataMap},
         metric::{Metric, MetricType},
     };
@@ -171,4 +349,157 @@ mod tests {
             expected
         );
     }
+
+    #[tokio::test]
+    async fn test_nodes_adaptive_selection() {
+        let stats: NodesResponse =
+            serde_json::from_str(include_str!("../../tests/files/nodes_stats.json"))
+                .expect("valid json");
+
+        let expected_name: String = "m1-nodename.example.com".into();
+
+        let mut metadata = NodeDataMap::new();
+        let _ = metadata.insert(
+            "U-WnGaTpRxucgde3miiDWw".into(),
+            NodeData {
+                name: expected_name.clone(),
+                ..Default::default()
+            },
+        );
+        let metadata = RwLock::new(metadata);
+        let mut values = stats.into_values(&metadata, REMOVE_KEYS).await;
+
+        adaptive_selection(&mut values, &metadata).await;
+
+        let metrics = metric::from_values(values);
+
+        let expected = vec![
+            Metric(
+                "adaptive_selection_avg_queue_size".into(),
+                MetricType::Gauge(0),
+            ),
+            Metric(
+                "adaptive_selection_avg_response_time_seconds".into(),
+                MetricType::Time(Duration::from_nanos(196669342)),
+            ),
+            Metric(
+                "adaptive_selection_avg_service_time_seconds".into(),
+                MetricType::Time(Duration::from_nanos(71587396)),
+            ),
+            Metric("ip".into(), MetricType::Label("".into())),
+            Metric("name".into(), MetricType::Label(expected_name.clone())),
+            Metric(
+                "adaptive_selection_outgoing_searches".into(),
+                MetricType::Gauge(0),
+            ),
+            Metric("adaptive_selection_rank".into(), MetricType::GaugeF(196.7)),
+            Metric(
+                "adaptive_selection_target_node".into(),
+                MetricType::Label(expected_name.clone()),
+            ),
+            Metric("vin_cluster_version".into(), MetricType::Label("".into())),
+        ];
+
+        assert!(
+            metrics.contains(&expected),
+            "got {:?}\nexpected {:?}",
+            metrics,
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_nodes_breakers_and_indexing_pressure() {
+        let stats: NodesResponse =
+            serde_json::from_str(include_str!("../../tests/files/nodes_stats.json"))
+                .expect("valid json");
+
+        let mut metadata = NodeDataMap::new();
+        let _ = metadata.insert(
+            "U-WnGaTpRxucgde3miiDWw".into(),
+            NodeData {
+                name: "m1-nodename.example.com".into(),
+                ..Default::default()
+            },
+        );
+        let metadata = RwLock::new(metadata);
+        let mut values = stats.into_values(&metadata, REMOVE_KEYS).await;
+
+        let mut labeled = Vec::new();
+
+        for value in values.iter_mut() {
+            labeled.extend(breakers(value));
+            labeled.extend(indexing_pressure(value));
+        }
+
+        // Node stats no longer have breakers nor indexing pressure
+        assert!(values.iter().all(
+            |value| value.get("breakers").is_none() && value.get("indexing_pressure").is_none()
+        ));
+
+        let metrics = metric::from_values_with_rules(labeled, &rules());
+
+        let fielddata = metrics
+            .iter()
+            .find(|metrics| {
+                metrics.contains(&Metric(
+                    "breaker".into(),
+                    MetricType::Label("fielddata".into()),
+                ))
+            })
+            .expect("fielddata breaker");
+        assert!(fielddata.contains(&Metric(
+            "breakers_overhead".into(),
+            MetricType::GaugeF(1.03)
+        )));
+        assert!(fielddata.contains(&Metric(
+            "name".into(),
+            MetricType::Label("m1-nodename.example.com".into())
+        )));
+
+        // Zero breaker trips and rejections are collected
+        let options = crate::ExporterOptions {
+            exporter_skip_zero_metrics: false,
+            ..Default::default()
+        };
+        let mut collection = Collection::new("test_nodes_stats", options);
+        collection.include_labels = vec!["name".into(), "breaker".into(), "stage".into()];
+
+        for metrics in metrics {
+            collection.collect(metrics).unwrap();
+        }
+
+        // Series per breaker and per stage instead of metric per breaker
+        let node = "m1-nodename.example.com";
+        let overhead = &collection.fgauges["breakers_overhead"];
+        assert_eq!(overhead.with_label_values(&["fielddata", node]).get(), 1.03);
+        assert_eq!(
+            overhead
+                .with_label_values(&["in_flight_requests", node])
+                .get(),
+            2.0
+        );
+
+        let tripped = &collection.counters["breakers_tripped"];
+        assert_eq!(tripped.with_label_values(&["parent", node]).get(), 0);
+
+        let total = &collection.gauges["indexing_pressure_total_bytes"];
+        assert_eq!(
+            total.with_label_values(&[node, "primary"]).get(),
+            835347051791
+        );
+        assert_eq!(
+            collection.counters["indexing_pressure_rejections"]
+                .with_label_values(&[node, "replica"])
+                .get(),
+            0
+        );
+
+        assert!(!collection
+            .gauges
+            .keys()
+            .chain(collection.fgauges.keys())
+            .chain(collection.counters.keys())
+            .any(|key| key.starts_with("breakers_fielddata") || key.contains("breakers_breaker")));
+    }
 }
@@ -0,0 +1,30 @@
+use elasticsearch::SearchParts;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::query::QueryConfig;
+use crate::Exporter;
+
+async fn metrics(
+    exporter: &Exporter,
+    query: &QueryConfig,
+) -> Result<Vec<Value>, elasticsearch::Error> {
+    let response = exporter
+        .client()
+        .search(SearchParts::Index(&query.indices()))
+        .body(query.body())
+        .request_timeout(exporter.options().timeout_for_subsystem(query.subsystem()))
+        .send()
+        .await?;
+
+    Ok(query.values(&response.json::<Value>().await?))
+}
+
+/// Poll custom search query as `query_<name>` subsystem
+pub(crate) async fn poll(exporter: Exporter, query: Arc<QueryConfig>) {
+    crate::metrics::poll(exporter, query.subsystem(), Vec::new(), |exporter| {
+        let query = query.clone();
+        async move { metrics(&exporter, &query).await }
+    })
+    .await
+}
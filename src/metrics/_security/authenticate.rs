@@ -0,0 +1,99 @@
+use serde_json::json;
+
+pub(crate) const SUBSYSTEM: &str = "security_authenticate";
+
+/// Authentication failures are exported as metric instead of being only
+/// logged as poll errors
+const AUTHENTICATION_ERRORS: &[u16] = &[401, 403];
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/security-api-authenticate.html
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let response = exporter
+        .client()
+        .security()
+        .authenticate()
+        .request_timeout(exporter.options().timeout_for_subsystem(SUBSYSTEM))
+        .send()
+        .await?;
+
+    let status_code = response.status_code().as_u16();
+
+    // Credentials are configured as user info of `elasticsearch_url`
+    let username = exporter.options().elasticsearch_url.username();
+
+    if AUTHENTICATION_ERRORS.contains(&status_code) {
+        return Ok(vec![authenticated_value(username, 0, status_code)]);
+    }
+
+    let user = response.error_for_status_code()?.json::<Value>().await?;
+
+    Ok(vec![
+        authenticated_value(username, 1, status_code),
+        realm_value(&user),
+    ])
+}
+
+/// Labeled only by configured username, so that failure replaces the last
+/// good series instead of being exported next to it
+fn authenticated_value(username: &str, authenticated: u8, status_code: u16) -> Value {
+    json!({
+        "username": username,
+        "authenticated": authenticated,
+        "status_code": status_code,
+    })
+}
+
+/// Exporter own user and realm it was authenticated by, unknown on failure
+fn realm_value(user: &Value) -> Value {
+    json!({
+        "username": user["username"],
+        "realm": user["authentication_realm"]["name"],
+        "realm_type": user["authentication_realm"]["type"],
+        "info": 1,
+    })
+}
+
+crate::poll_metrics!();
+
+#[test]
+fn test_security_authenticate() {
+    let user = json!({
+        "username": "exporter",
+        "roles": ["monitoring_user"],
+        "full_name": null,
+        "email": null,
+        "metadata": {},
+        "enabled": true,
+        "authentication_realm": {"name": "default_native", "type": "native"},
+        "lookup_realm": {"name": "default_native", "type": "native"},
+        "authentication_type": "realm"
+    });
+
+    assert_eq!(
+        realm_value(&user),
+        json!({
+            "username": "exporter",
+            "realm": "default_native",
+            "realm_type": "native",
+            "info": 1,
+        })
+    );
+
+    assert_eq!(
+        authenticated_value("exporter", 1, 200),
+        json!({
+            "username": "exporter",
+            "authenticated": 1,
+            "status_code": 200,
+        })
+    );
+
+    assert_eq!(
+        authenticated_value("exporter", 0, 401),
+        json!({
+            "username": "exporter",
+            "authenticated": 0,
+            "status_code": 401,
+        })
+    );
+}
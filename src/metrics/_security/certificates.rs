@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+pub(crate) const SUBSYSTEM: &str = "ssl_certificates";
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/security-api-ssl.html
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let response = exporter
+        .client()
+        .ssl()
+        .certificates()
+        .request_timeout(exporter.options().timeout_for_subsystem(SUBSYSTEM))
+        .send()
+        .await?;
+
+    let certificates = response.json::<Vec<Value>>().await?;
+
+    Ok(into_values(&certificates, Utc::now()))
+}
+
+/// Seconds until expiry of each certificate, negative once expired
+fn into_values(certificates: &[Value], now: DateTime<Utc>) -> Vec<Value> {
+    certificates
+        .iter()
+        .filter_map(|certificate| {
+            let expiry = DateTime::parse_from_rfc3339(certificate["expiry"].as_str()?).ok()?;
+
+            Some(json!({
+                "path": certificate["path"],
+                // PEM certificates have null alias
+                "alias": certificate["alias"].as_str().unwrap_or_default(),
+                "subject_dn": certificate["subject_dn"],
+                "expiry_remaining_seconds": (expiry.with_timezone(&Utc) - now).num_seconds(),
+            }))
+        })
+        .collect()
+}
+
+crate::poll_metrics!();
+
+#[test]
+fn test_ssl_certificates() {
+    let certificates: Vec<Value> = serde_json::from_value(json!([
+        {
+            "path": "certs/http.p12",
+            "format": "PKCS12",
+            "alias": "http",
+            "subject_dn": "CN=es01",
+            "serial_number": "55c6d1a2",
+            "has_private_key": true,
+            "expiry": "2024-01-02T00:00:00.000Z"
+        },
+        {
+            "path": "certs/ca.crt",
+            "format": "PEM",
+            "alias": null,
+            "subject_dn": "CN=Elastic Certificate Tool Autogenerated CA",
+            "serial_number": "a1b2c3",
+            "has_private_key": false,
+            "expiry": "2023-12-31T00:00:00.000Z"
+        },
+        {"path": "certs/broken.crt", "expiry": "never"}
+    ]))
+    .unwrap();
+
+    let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    let values = into_values(&certificates, now);
+    assert_eq!(values.len(), 2);
+    assert_eq!(
+        values[0],
+        json!({
+            "path": "certs/http.p12",
+            "alias": "http",
+            "subject_dn": "CN=es01",
+            "expiry_remaining_seconds": 86400,
+        })
+    );
+    assert_eq!(values[1]["alias"], "");
+    assert_eq!(values[1]["expiry_remaining_seconds"], -86400);
+}
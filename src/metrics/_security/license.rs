@@ -0,0 +1,79 @@
+use chrono::Utc;
+use serde_json::json;
+
+pub(crate) const SUBSYSTEM: &str = "license";
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/get-license.html
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
+    let response = exporter
+        .client()
+        .license()
+        .get()
+        .request_timeout(exporter.options().timeout_for_subsystem(SUBSYSTEM))
+        .send()
+        .await?;
+
+    let license = response.json::<Value>().await?;
+
+    Ok(into_values(&license, Utc::now().timestamp_millis()))
+}
+
+/// License type and status labels with expiry, `basic` license has no expiry
+fn into_values(license: &Value, now_millis: i64) -> Vec<Value> {
+    let license = &license["license"];
+
+    let mut value = json!({
+        "type": license["type"],
+        "status": license["status"],
+        "active": u8::from(license["status"] == "active"),
+    });
+
+    if let Some(expiry) = license["expiry_date_in_millis"].as_i64() {
+        value["expiry_timestamp_millis"] = expiry.into();
+        // Negative once expired
+        value["expiry_remaining_seconds"] = ((expiry - now_millis) / 1000).into();
+    }
+
+    vec![value]
+}
+
+crate::poll_metrics!();
+
+#[test]
+fn test_license() {
+    let license = json!({
+        "license": {
+            "status": "active",
+            "uid": "cbff45e7-c553-41f7-ae4f-9205eabd80xx",
+            "type": "platinum",
+            "issue_date": "2018-10-16T07:02:48.024Z",
+            "issue_date_in_millis": 1539673368024_i64,
+            "expiry_date": "2030-01-01T00:00:00.000Z",
+            "expiry_date_in_millis": 1893456000000_i64,
+            "max_nodes": 100,
+            "issued_to": "example",
+            "issuer": "elasticsearch",
+            "start_date_in_millis": -1
+        }
+    });
+
+    let values = into_values(&license, 1893456000000 - 3600 * 1000);
+    assert_eq!(
+        values,
+        vec![json!({
+            "type": "platinum",
+            "status": "active",
+            "active": 1,
+            "expiry_timestamp_millis": 1893456000000_i64,
+            "expiry_remaining_seconds": 3600,
+        })]
+    );
+
+    let basic = json!({"license": {"status": "active", "type": "basic", "issue_date_in_millis": 1539673368024_i64}});
+    let values = into_values(&basic, 1893456000000);
+    assert!(values[0].get("expiry_timestamp_millis").is_none());
+
+    let metrics = crate::metric::from_values(values);
+    assert!(metrics[0].iter().any(|metric| metric.key() == "type"
+        && *metric.metric_type() == crate::metric::MetricType::Label("basic".into())));
+}
@@ -0,0 +1,3 @@
+pub(crate) mod authenticate;
+pub(crate) mod certificates;
+pub(crate) mod license;
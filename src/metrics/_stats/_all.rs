@@ -4,7 +4,7 @@ use super::responses::StatsResponse;
 
 pub(crate) const SUBSYSTEM: &str = "stats";
 
-async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Error> {
+async fn metrics(exporter: &Exporter) -> Result<Vec<Value>, elasticsearch::Error> {
     let fields = exporter.options().query_fields_for_subsystem(SUBSYSTEM);
     let filter_path = exporter
         .options()
@@ -32,7 +32,7 @@ async fn metrics(exporter: &Exporter) -> Result<Vec<Metrics>, elasticsearch::Err
         .into_values(REMOVE_KEYS)
         .await;
 
-    Ok(metric::from_values(values))
+    Ok(values)
 }
 
 const REMOVE_KEYS: &[&str] = &["uuid"];
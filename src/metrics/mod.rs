@@ -1,6 +1,11 @@
 pub(crate) mod _cat;
 pub(crate) mod _cluster;
+pub(crate) mod _endpoint;
+pub(crate) mod _freshness;
+pub(crate) mod _indices;
 pub(crate) mod _nodes;
+pub(crate) mod _query;
+pub(crate) mod _security;
 pub(crate) mod _stats;
 
 // TODO: add metrics of
@@ -8,140 +13,271 @@ pub(crate) mod _stats;
 // - https://www.elastic.co/guide/en/elasticsearch/reference/current/tasks.html
 // - https://www.elastic.co/guide/en/elasticsearch/reference/current/cluster-state.html
 
-/// Convenience macro to poll metrics
+use serde_json::Value;
+use std::cmp;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::collection::{lifetime, lifetime::MetricLifetimeMap, Collection};
+use crate::metric;
+use crate::Exporter;
+
+/// Convenience macro to poll metrics, subsystem metric type rules are
+/// passed as function name, e.g.: `poll_metrics!(rules)`
 #[macro_export]
 macro_rules! poll_metrics {
     () => {
+        $crate::poll_metrics!(@rules Vec::new());
+    };
+    ($rules:ident) => {
+        $crate::poll_metrics!(@rules $rules());
+    };
+    (@rules $rules:expr) => {
         #[allow(unused)]
         use serde_json::Value;
-        use std::cmp;
+        #[allow(unused)]
         use std::time::Duration;
 
-        use $crate::collection::{lifetime, lifetime::MetricLifetimeMap, Collection};
-        use $crate::metric::{self, Metrics};
+        #[allow(unused)]
+        use $crate::metric;
         use $crate::Exporter;
 
         #[allow(unused)]
         pub(crate) async fn poll(exporter: Exporter) {
-            let options = exporter.options();
+            $crate::metrics::poll(exporter, SUBSYSTEM, $rules, |exporter| async move {
+                metrics(&exporter).await
+            })
+            .await
+        }
+    };
+}
 
-            let mut collection = Collection::new(SUBSYSTEM, options.clone());
-            // Common to all /_cat metrics
-            collection.const_labels = exporter.const_labels();
+/// Poll subsystem metrics on poll interval or on-demand request, collect
+/// polled metrics and remove series outdated by metric lifetime
+///
+/// Subsystem own metric type rules are applied after user configured rules
+pub(crate) async fn poll<F, Fut>(
+    exporter: Exporter,
+    subsystem: &'static str,
+    rules: Vec<metric::MetricRule>,
+    metrics: F,
+) where
+    F: Fn(Exporter) -> Fut,
+    Fut: Future<Output = Result<Vec<Value>, elasticsearch::Error>>,
+{
+    let options = exporter.options();
 
-            if let Some(skip_labels) = options.exporter_skip_labels.get(SUBSYSTEM) {
-                collection.skip_labels = skip_labels.clone();
-            }
+    let mut collection = Collection::new(subsystem, options.clone());
+    // Common to all /_cat metrics
+    collection.const_labels = exporter.const_labels();
 
-            if let Some(skip_metrics) = options.exporter_skip_metrics.get(SUBSYSTEM) {
-                collection.skip_metrics = skip_metrics.clone();
-            }
+    if let Some(skip_labels) = options.exporter_skip_labels.get(subsystem) {
+        collection.skip_labels = skip_labels.clone();
+    }
 
-            if let Some(include_labels) = options.exporter_include_labels.get(SUBSYSTEM) {
-                collection.include_labels = include_labels.clone();
-            }
+    if let Some(skip_metrics) = options.exporter_skip_metrics.get(subsystem) {
+        collection.skip_metrics = skip_metrics.clone();
+    }
 
-            let start =
-                tokio::time::Instant::now() + Duration::from_millis(Exporter::random_delay());
+    collection.include_labels = options.include_labels_for_subsystem(subsystem);
 
-            let poll_interval = options
-                .exporter_poll_intervals
-                .get(SUBSYSTEM)
-                .unwrap_or(&options.exporter_poll_default_interval);
+    collection.relabel_configs = options.relabel_configs_for_subsystem(subsystem).to_vec();
+    collection.sinks = exporter.sinks().to_vec();
+    collection.max_series = options.max_series_for_subsystem(subsystem);
 
-            let metric_lifetime = options
-                .exporter_metrics_lifetime_interval
-                .get(SUBSYSTEM)
-                .unwrap_or(&options.exporter_metrics_lifetime_default_interval);
+    let start = tokio::time::Instant::now() + Duration::from_millis(Exporter::random_delay());
 
-            // Metric lifetime must be more of the same than poll interval else metrics will get deleted sooner
-            let metric_lifetime = *cmp::max(poll_interval, metric_lifetime);
+    let poll_interval = options
+        .exporter_poll_intervals
+        .get(subsystem)
+        .unwrap_or(&options.exporter_poll_default_interval);
 
-            info!(
-                "Starting subsystem: {} with poll interval: {}sec lifetime: {}sec",
-                SUBSYSTEM,
-                poll_interval.as_secs(),
-                metric_lifetime.as_secs(),
-            );
+    let metric_lifetime = options
+        .exporter_metrics_lifetime_interval
+        .get(subsystem)
+        .unwrap_or(&options.exporter_metrics_lifetime_default_interval);
 
-            let mut interval = tokio::time::interval_at(start, *poll_interval);
-            // Convert to chrono Duration by overriding variable
-            let metric_lifetime = chrono::TimeDelta::try_seconds(metric_lifetime.as_secs() as i64)
-                .expect(
-                    format!(
-                        "Invalid subsytem {} metric lifetime: {}",
-                        SUBSYSTEM,
-                        metric_lifetime.as_secs()
-                    )
-                    .as_str(),
-                );
+    // Metric lifetime must be more of the same than poll interval else metrics will get deleted sooner
+    let metric_lifetime = *cmp::max(poll_interval, metric_lifetime);
+
+    // On-demand mode collects subsystem on scrape instead of interval
+    let mut on_demand = options
+        .exporter_on_demand
+        .then(|| exporter.on_demand_subscribe(subsystem));
+
+    if on_demand.is_some() {
+        info!(
+            "Starting on-demand subsystem: {} lifetime: {}sec",
+            subsystem,
+            metric_lifetime.as_secs(),
+        );
+    } else {
+        info!(
+            "Starting subsystem: {} with poll interval: {}sec lifetime: {}sec",
+            subsystem,
+            poll_interval.as_secs(),
+            metric_lifetime.as_secs(),
+        );
+    }
+
+    let mut metric_type_rules = options.metric_type_rules_for_subsystem(subsystem);
+    metric_type_rules.extend(rules);
+    // Interned metric keys are reused across polls
+    let mut flattener = metric::Flattener::default();
+
+    let mut interval = tokio::time::interval_at(start, *poll_interval);
+    // Convert to chrono Duration by overriding variable
+    let metric_lifetime = chrono::TimeDelta::try_seconds(metric_lifetime.as_secs() as i64)
+        .unwrap_or_else(|| {
+            panic!(
+                "Invalid subsytem {} metric lifetime: {}",
+                subsystem,
+                metric_lifetime.as_secs()
+            )
+        });
 
-            loop {
-                let now = lifetime::now() - metric_lifetime;
+    loop {
+        let mut now = lifetime::now() - metric_lifetime;
 
+        let request = match on_demand.as_mut() {
+            Some(requests) => {
+                let Some(request) = requests.recv().await else {
+                    break;
+                };
+                now = lifetime::now() - metric_lifetime;
+                Some(request)
+            }
+            None => {
                 let _ = interval.tick().await;
+                None
+            }
+        };
+
+        let timer = exporter
+            .metrics()
+            .subsystem_request_histogram
+            .with_label_values(&[&format!("/{}", subsystem), exporter.cluster_name()])
+            .start_timer();
 
-                let timer = exporter
-                    .metrics()
-                    .subsystem_request_histogram
-                    .with_label_values(&[&format!("/{}", SUBSYSTEM), exporter.cluster_name()])
-                    .start_timer();
-
-                match metrics(&exporter).await {
-                    Ok(metrics) => {
-                        for metric in metrics.into_iter() {
-                            let _ = collection.collect(metric);
-                        }
-                    }
-                    Err(e) => {
-                        error!("poll {} metrics err {}", collection.subsystem(), e);
-                    }
+        match metrics(exporter.clone()).await {
+            Ok(values) => {
+                for metric in flattener.flatten(&values, &metric_type_rules) {
+                    let _ = collection.collect(metric);
                 }
 
-                timer.observe_duration();
+                exporter.set_last_collect(subsystem, chrono::Utc::now().timestamp_millis());
+            }
+            Err(e) => {
+                error!("poll {} metrics err {}", collection.subsystem(), e);
+            }
+        }
+
+        timer.observe_duration();
+
+        let dropped = collection.take_dropped();
+
+        if !dropped.is_empty() {
+            warn!(
+                "`{}` reached max series {} dropped {} new series of {} metrics",
+                subsystem,
+                collection.max_series,
+                dropped.values().sum::<u64>(),
+                dropped.len()
+            );
+            exporter.add_series_dropped(subsystem, dropped);
+        }
+
+        for (k, v) in collection
+            .gauges_lifetime
+            .extract_if(|_k, v| v.is_outdated(now))
+            .collect::<MetricLifetimeMap>()
+            .iter()
+        {
+            debug!(
+                "REMOVING `{}` stale metric: {} labels: {:?}",
+                subsystem, v.metric_key, v.label_values
+            );
+            let _ = collection.gauges_series.remove(k);
+            if let Some(gauge) = collection.gauges.get(&v.metric_key) {
+                let _ = gauge.remove_label_values(
+                    &v.label_values
+                        .iter()
+                        .map(|lv| lv.as_str())
+                        .collect::<Vec<&str>>(),
+                );
+            }
+        }
 
-                for (_, v) in collection
-                    .gauges_lifetime
-                    .extract_if(|_k, v| v.is_outdated(now))
-                    .collect::<MetricLifetimeMap>()
+        for (k, v) in collection
+            .fgauges_lifetime
+            .extract_if(|_k, v| v.is_outdated(now))
+            .collect::<MetricLifetimeMap>()
+            .iter()
+        {
+            debug!(
+                "REMOVING `{}` stale metric: {} labels: {:?}",
+                subsystem, v.metric_key, v.label_values
+            );
+            let _ = collection.fgauges_series.remove(k);
+            if let Some(fgauge) = collection.fgauges.get(&v.metric_key) {
+                let _ = fgauge.remove_label_values(
+                    &v.label_values
+                        .iter()
+                        .map(|lv| lv.as_str())
+                        .collect::<Vec<&str>>(),
+                );
+            }
+        }
+
+        for (k, v) in collection
+            .counters_lifetime
+            .extract_if(|_k, v| v.is_outdated(now))
+            .collect::<MetricLifetimeMap>()
+            .iter()
+        {
+            debug!(
+                "REMOVING `{}` stale metric: {} labels: {:?}",
+                subsystem, v.metric_key, v.label_values
+            );
+            let _ = collection.counters_series.remove(k);
+            if let Some(counter) = collection.counters.get(&v.metric_key) {
+                let label_values = v
+                    .label_values
                     .iter()
-                {
-                    debug!(
-                        "REMOVING `{}` stale metric: {} labels: {:?}",
-                        SUBSYSTEM, v.metric_key, v.label_values
-                    );
-                    if let Some(gauge) = collection.gauges.get(&v.metric_key) {
-                        gauge.remove_label_values(
-                            &v.label_values
-                                .iter()
-                                .map(|lv| lv.as_str())
-                                .collect::<Vec<&str>>(),
-                        );
-                    }
-                }
+                    .map(|lv| lv.as_str())
+                    .collect::<Vec<&str>>();
 
-                for (_, v) in collection
-                    .fgauges_lifetime
-                    .extract_if(|_k, v| v.is_outdated(now))
-                    .collect::<MetricLifetimeMap>()
+                let _ = counter.remove_label_values(&label_values);
+                crate::collection::created::remove(counter, &label_values);
+            }
+        }
+
+        for (_, v) in collection
+            .histograms_lifetime
+            .extract_if(|_k, v| v.is_outdated(now))
+            .collect::<MetricLifetimeMap>()
+            .iter()
+        {
+            debug!(
+                "REMOVING `{}` stale metric: {} labels: {:?}",
+                subsystem, v.metric_key, v.label_values
+            );
+            if let Some(histogram) = collection.histograms.get(&v.metric_key) {
+                let label_values = v
+                    .label_values
                     .iter()
-                {
-                    debug!(
-                        "REMOVING `{}` stale metric: {} labels: {:?}",
-                        SUBSYSTEM, v.metric_key, v.label_values
-                    );
-                    if let Some(fgauge) = collection.fgauges.get(&v.metric_key) {
-                        fgauge.remove_label_values(
-                            &v.label_values
-                                .iter()
-                                .map(|lv| lv.as_str())
-                                .collect::<Vec<&str>>(),
-                        );
-                    }
-                }
+                    .map(|lv| lv.as_str())
+                    .collect::<Vec<&str>>();
+
+                let _ = histogram.remove_label_values(&label_values);
+                crate::collection::created::remove(histogram, &label_values);
             }
         }
-    };
+
+        if let Some(request) = request {
+            let _ = request.send(());
+        }
+    }
 }
 
 #[cfg(test)]
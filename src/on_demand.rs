@@ -0,0 +1,130 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+/// Collection request, sender is notified once subsystem is collected
+pub(crate) type Request = oneshot::Sender<()>;
+
+/// Subsystem poller waiting for on-demand collection requests
+#[derive(Debug, Clone)]
+struct Subscriber {
+    subsystem: &'static str,
+    requests: mpsc::Sender<Request>,
+    /// Completion time of last collection, held locked during collection
+    /// so that overlapping scrapes wait for and share the same collection
+    last: Arc<tokio::sync::Mutex<Option<Instant>>>,
+}
+
+/// On-demand collection of subsystems triggered by scrape
+#[derive(Debug, Default)]
+pub(crate) struct OnDemand {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl OnDemand {
+    /// Subscribe subsystem poller to on-demand collection requests
+    pub(crate) fn subscribe(&self, subsystem: &'static str) -> mpsc::Receiver<Request> {
+        let (requests, receiver) = mpsc::channel(1);
+
+        self.subscribers
+            .lock()
+            .expect("on demand lock")
+            .push(Subscriber {
+                subsystem,
+                requests,
+                last: Default::default(),
+            });
+
+        receiver
+    }
+
+    /// Collect subsystems matching filter concurrently, subsystem collected
+    /// within `min_interval` is served from registry as is
+    pub(crate) async fn collect(
+        &self,
+        filter: impl Fn(&str) -> bool,
+        min_interval: Duration,
+        timeout: impl Fn(&'static str) -> Duration,
+    ) {
+        let subscribers = self
+            .subscribers
+            .lock()
+            .expect("on demand lock")
+            .iter()
+            .filter(|subscriber| filter(subscriber.subsystem))
+            .cloned()
+            .collect::<Vec<Subscriber>>();
+
+        let mut set = tokio::task::JoinSet::new();
+
+        for subscriber in subscribers.into_iter() {
+            let timeout = timeout(subscriber.subsystem);
+
+            let _ = set.spawn(async move {
+                let mut last = subscriber.last.lock().await;
+
+                if last.is_some_and(|last| last.elapsed() < min_interval) {
+                    return;
+                }
+
+                let (request, done) = oneshot::channel();
+
+                let collect = async {
+                    subscriber.requests.send(request).await.ok()?;
+                    done.await.ok()
+                };
+
+                match tokio::time::timeout(timeout, collect).await {
+                    Ok(Some(())) => *last = Some(Instant::now()),
+                    Ok(None) => error!("on demand {} poller stopped", subscriber.subsystem),
+                    Err(_) => warn!(
+                        "on demand {} collection timed out after {:?}",
+                        subscriber.subsystem, timeout
+                    ),
+                }
+            });
+        }
+
+        while set.join_next().await.is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_on_demand_collect() {
+        let on_demand = Arc::new(OnDemand::default());
+        let mut requests = on_demand.subscribe("cat_health");
+        let _shards = on_demand.subscribe("cat_shards");
+
+        let poller = tokio::spawn(async move {
+            let mut collected = 0;
+            while let Some(done) = requests.recv().await {
+                collected += 1;
+                let _ = done.send(());
+            }
+            collected
+        });
+
+        let min_interval = Duration::from_secs(60);
+        let timeout = |_| Duration::from_millis(100);
+
+        // Overlapping scrapes share single collection
+        let _ = tokio::join!(
+            on_demand.collect(|subsystem| subsystem == "cat_health", min_interval, timeout),
+            on_demand.collect(|subsystem| subsystem == "cat_health", min_interval, timeout),
+        );
+        // Served from cache within min interval
+        on_demand
+            .collect(|subsystem| subsystem == "cat_health", min_interval, timeout)
+            .await;
+        // cat_shards poller never answers, collection is bounded by timeout
+        on_demand.collect(|_| true, min_interval, timeout).await;
+
+        drop(on_demand);
+        assert_eq!(poller.await.unwrap(), 1);
+    }
+}
@@ -3,7 +3,15 @@ use std::time::Duration;
 use std::{fmt, path::PathBuf};
 use url::Url;
 
-use crate::{metrics, CollectionLabels, ExporterMetricsSwitch, ExporterPollIntervals};
+use crate::collection::relabel::RelabelConfig;
+use crate::endpoint::EndpointConfig;
+use crate::freshness::FreshnessConfig;
+use crate::metric::MetricRule;
+use crate::query::QueryConfig;
+use crate::{
+    metrics, CollectionLabels, ExporterMaxSeries, ExporterMetricsSwitch, ExporterPollIntervals,
+    MetricTypeRules, PushHeaders, RelabelConfigs,
+};
 
 /// Certificate validation options
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +94,11 @@ pub struct ExporterOptions {
     pub exporter_skip_metrics: CollectionLabels,
     /// Exporter skip zero metrics
     pub exporter_skip_zero_metrics: bool,
+    /// Exporter metric type rules consulted before built-in metric type rules
+    pub exporter_metric_type_rules: MetricTypeRules,
+    /// Exporter relabel configs applied to metric key and labels before
+    /// metric is registered
+    pub exporter_relabel_configs: RelabelConfigs,
     /// Exporter metrics switch either ON or OFF
     pub exporter_metrics_enabled: ExporterMetricsSwitch,
     /// Export metrics namespace
@@ -102,6 +115,125 @@ pub struct ExporterOptions {
     pub exporter_metrics_lifetime_interval: ExporterPollIntervals,
     /// Metrics metrics lifetime
     pub exporter_metrics_lifetime_default_interval: Duration,
+
+    /// Exporter max labeled series per subsystem
+    pub exporter_max_series: ExporterMaxSeries,
+    /// Exporter default max labeled series of subsystem, 0 is unlimited
+    pub exporter_max_series_default: usize,
+
+    /// Shard store size in bytes over which shards are counted by `cat_shards_summary`
+    pub exporter_shard_size_threshold: u64,
+
+    /// Prometheus remote_write URL, push mode is enabled if set
+    pub exporter_remote_write_url: Option<Url>,
+    /// Remote write push interval
+    pub exporter_remote_write_interval: Duration,
+    /// Remote write HTTP request timeout
+    pub exporter_remote_write_timeout: Duration,
+    /// Remote write HTTP headers, e.g.: Authorization
+    pub exporter_remote_write_headers: PushHeaders,
+    /// Remote write in-memory queue size of pending payloads
+    pub exporter_remote_write_queue_size: usize,
+    /// Remote write retries of a payload before it is retried on next interval
+    pub exporter_remote_write_max_retries: usize,
+
+    /// OpenTelemetry OTLP/HTTP metrics URL, e.g.: http://localhost:4318/v1/metrics,
+    /// OTLP export is enabled if set
+    pub exporter_otlp_url: Option<Url>,
+    /// OTLP export interval
+    pub exporter_otlp_interval: Duration,
+    /// OTLP request timeout
+    pub exporter_otlp_timeout: Duration,
+    /// OTLP HTTP headers, e.g.: Authorization
+    pub exporter_otlp_headers: PushHeaders,
+    /// OTLP `service.instance.id` resource attribute, falls back to hostname
+    pub exporter_otlp_instance: Option<String>,
+
+    /// Subsystems exported with explicit sample timestamp of poll completion
+    pub exporter_sample_timestamps: ExporterMetricsSwitch,
+
+    /// Collect subsystems on scrape instead of polling on interval
+    pub exporter_on_demand: bool,
+    /// Minimum interval between on-demand collections of a subsystem,
+    /// scrapes within interval are served from cache
+    pub exporter_on_demand_min_interval: Duration,
+
+    /// DogStatsD UDP address, e.g.: 127.0.0.1:8125, StatsD sink is enabled if set
+    pub exporter_statsd_addr: Option<String>,
+    /// DogStatsD metric name prefix
+    pub exporter_statsd_prefix: String,
+    /// DogStatsD sink flush interval
+    pub exporter_statsd_flush_interval: Duration,
+
+    /// Graphite plaintext TCP address, e.g.: 127.0.0.1:2003, Graphite sink is enabled if set
+    pub exporter_graphite_addr: Option<String>,
+    /// Graphite metric path prefix
+    pub exporter_graphite_prefix: String,
+    /// Graphite sink flush interval
+    pub exporter_graphite_flush_interval: Duration,
+
+    /// Custom search queries polled as `query_<name>` subsystems
+    pub exporter_queries: Vec<QueryConfig>,
+    /// User defined REST endpoints polled as `endpoint_<name>` subsystems
+    pub exporter_endpoints: Vec<EndpointConfig>,
+    /// Index groups of `freshness` subsystem polled for their latest document
+    pub exporter_freshness: Vec<FreshnessConfig>,
+}
+
+impl Default for ExporterOptions {
+    /// Options of local cluster with CLI default intervals and timeouts,
+    /// per subsystem options are left empty
+    fn default() -> Self {
+        Self {
+            elasticsearch_url: Url::parse("http://127.0.0.1:9200").expect("valid url"),
+            elasticsearch_global_timeout: Duration::from_secs(30),
+            elasticsearch_query_fields: Default::default(),
+            elasticsearch_query_filter_path: Default::default(),
+            elasticsearch_subsystem_timeouts: Default::default(),
+            elasticsearch_path_parameters: Default::default(),
+            elasticsearch_certificate_path: None,
+            elasticsearch_certificate_validation: None,
+            exporter_skip_labels: Default::default(),
+            exporter_include_labels: Default::default(),
+            exporter_skip_metrics: Default::default(),
+            exporter_skip_zero_metrics: true,
+            exporter_metric_type_rules: Default::default(),
+            exporter_relabel_configs: Default::default(),
+            exporter_metrics_enabled: Default::default(),
+            exporter_metrics_namespace: "elasticsearch".into(),
+            exporter_metadata_refresh_interval: Duration::from_secs(180),
+            exporter_poll_default_interval: Duration::from_secs(15),
+            exporter_poll_intervals: Default::default(),
+            exporter_metrics_lifetime_interval: Default::default(),
+            exporter_metrics_lifetime_default_interval: Duration::from_secs(15),
+            exporter_max_series: Default::default(),
+            exporter_max_series_default: 0,
+            exporter_shard_size_threshold: 50 * 1024 * 1024 * 1024,
+            exporter_remote_write_url: None,
+            exporter_remote_write_interval: Duration::from_secs(15),
+            exporter_remote_write_timeout: Duration::from_secs(30),
+            exporter_remote_write_headers: Default::default(),
+            exporter_remote_write_queue_size: 64,
+            exporter_remote_write_max_retries: 3,
+            exporter_otlp_url: None,
+            exporter_otlp_interval: Duration::from_secs(15),
+            exporter_otlp_timeout: Duration::from_secs(30),
+            exporter_otlp_headers: Default::default(),
+            exporter_otlp_instance: None,
+            exporter_sample_timestamps: Default::default(),
+            exporter_on_demand: false,
+            exporter_on_demand_min_interval: Duration::from_secs(5),
+            exporter_statsd_addr: None,
+            exporter_statsd_prefix: "elasticsearch".into(),
+            exporter_statsd_flush_interval: Duration::from_secs(10),
+            exporter_graphite_addr: None,
+            exporter_graphite_prefix: "elasticsearch".into(),
+            exporter_graphite_flush_interval: Duration::from_secs(60),
+            exporter_queries: Vec::new(),
+            exporter_endpoints: Vec::new(),
+            exporter_freshness: Vec::new(),
+        }
+    }
 }
 
 impl ExporterOptions {
@@ -143,6 +275,67 @@ impl ExporterOptions {
             .unwrap_or_default()
     }
 
+    /// Metric type rules for subsystems
+    pub fn metric_type_rules_for_subsystem(&self, subsystem: &'static str) -> Vec<MetricRule> {
+        let mut rules = self
+            .exporter_metric_type_rules
+            .get(subsystem)
+            .cloned()
+            .unwrap_or_default();
+
+        // Bucket keys of custom queries are labels regardless of value
+        if let Some(query) = self.query_for_subsystem(subsystem) {
+            rules.extend(query.rules().iter().cloned());
+        }
+
+        if let Some(endpoint) = self.endpoint_for_subsystem(subsystem) {
+            rules.extend(endpoint.rules().iter().cloned());
+        }
+
+        rules
+    }
+
+    /// Labels included into metrics of subsystem
+    pub fn include_labels_for_subsystem(&self, subsystem: &'static str) -> Vec<String> {
+        let mut labels = self
+            .exporter_include_labels
+            .get(subsystem)
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(query) = self.query_for_subsystem(subsystem) {
+            labels.extend(query.labels().iter().cloned());
+        }
+
+        if let Some(endpoint) = self.endpoint_for_subsystem(subsystem) {
+            labels.extend(endpoint.labels().iter().cloned());
+        }
+
+        labels
+    }
+
+    /// User defined REST endpoint of `endpoint_<name>` subsystem
+    pub fn endpoint_for_subsystem(&self, subsystem: &str) -> Option<&EndpointConfig> {
+        self.exporter_endpoints
+            .iter()
+            .find(|endpoint| endpoint.subsystem() == subsystem)
+    }
+
+    /// Custom search query of `query_<name>` subsystem
+    pub fn query_for_subsystem(&self, subsystem: &str) -> Option<&QueryConfig> {
+        self.exporter_queries
+            .iter()
+            .find(|query| query.subsystem() == subsystem)
+    }
+
+    /// Relabel configs for subsystems
+    pub fn relabel_configs_for_subsystem(&self, subsystem: &'static str) -> &[RelabelConfig] {
+        self.exporter_relabel_configs
+            .get(subsystem)
+            .map(|configs| configs.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Get timeout for subsystem or fallback to global
     pub fn timeout_for_subsystem(&self, subsystem: &'static str) -> Duration {
         *self
@@ -151,6 +344,22 @@ impl ExporterOptions {
             .unwrap_or(&self.elasticsearch_global_timeout)
     }
 
+    /// Max labeled series of subsystem, 0 is unlimited
+    pub fn max_series_for_subsystem(&self, subsystem: &str) -> usize {
+        self.exporter_max_series
+            .get(subsystem)
+            .copied()
+            .unwrap_or(self.exporter_max_series_default)
+    }
+
+    /// Whether subsystem samples carry explicit timestamp of poll completion
+    pub fn is_sample_timestamped(&self, subsystem: &str) -> bool {
+        self.exporter_sample_timestamps
+            .get(subsystem)
+            .copied()
+            .unwrap_or(false)
+    }
+
     /// /_cat subsystems
     pub fn cat_subsystems() -> &'static [&'static str] {
         use metrics::_cat::*;
@@ -158,6 +367,7 @@ impl ExporterOptions {
         &[
             allocation::SUBSYSTEM,
             shards::SUBSYSTEM,
+            shards_summary::SUBSYSTEM,
             indices::SUBSYSTEM,
             segments::SUBSYSTEM,
             nodes::SUBSYSTEM,
@@ -179,14 +389,23 @@ impl ExporterOptions {
     pub fn cluster_subsystems() -> &'static [&'static str] {
         use metrics::_cluster::*;
 
-        &[health::SUBSYSTEM]
+        &[
+            health::SUBSYSTEM,
+            settings::SUBSYSTEM,
+            watermarks::SUBSYSTEM,
+        ]
     }
 
     /// /_nodes subsystems
     pub fn nodes_subsystems() -> &'static [&'static str] {
         use metrics::_nodes::*;
 
-        &[usage::SUBSYSTEM, stats::SUBSYSTEM, info::SUBSYSTEM]
+        &[
+            usage::SUBSYSTEM,
+            stats::SUBSYSTEM,
+            info::SUBSYSTEM,
+            hot_threads::SUBSYSTEM,
+        ]
     }
 
     /// /_stats subsystems
@@ -195,6 +414,24 @@ impl ExporterOptions {
 
         &[_all::SUBSYSTEM]
     }
+
+    /// /_ssl, /_license and /_security subsystems
+    pub fn security_subsystems() -> &'static [&'static str] {
+        use metrics::_security::*;
+
+        &[
+            certificates::SUBSYSTEM,
+            license::SUBSYSTEM,
+            authenticate::SUBSYSTEM,
+        ]
+    }
+
+    /// /_settings and /_mapping subsystems
+    pub fn indices_subsystems() -> &'static [&'static str] {
+        use metrics::_indices::*;
+
+        &[settings::SUBSYSTEM]
+    }
 }
 
 fn switch_to_string(output: &mut String, field: &'static str, switches: &ExporterMetricsSwitch) {
@@ -219,6 +456,33 @@ fn collection_labels_to_string(
     }
 }
 
+fn rules_to_string(output: &mut String, field: &'static str, rules: &MetricTypeRules) {
+    output.push('\n');
+    output.push_str(&format!("{}:", field));
+    for (k, v) in rules.iter() {
+        output.push('\n');
+        output.push_str(&format!(
+            " - {}: {}",
+            k,
+            v.iter()
+                .map(|rule| rule.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        ));
+    }
+}
+
+fn relabel_configs_to_string(output: &mut String, field: &'static str, configs: &RelabelConfigs) {
+    output.push('\n');
+    output.push_str(&format!("{}:", field));
+    for (k, v) in configs.iter() {
+        for config in v.iter() {
+            output.push('\n');
+            output.push_str(&format!(" - {}: {}", k, config));
+        }
+    }
+}
+
 fn poll_duration_to_string(
     output: &mut String,
     field: &'static str,
@@ -266,6 +530,16 @@ impl fmt::Display for ExporterOptions {
             "Available /_stats subsystems",
             Self::stats_subsystems(),
         );
+        vec_to_string(
+            &mut output,
+            "Available /_settings subsystems",
+            Self::indices_subsystems(),
+        );
+        vec_to_string(
+            &mut output,
+            "Available /_ssl, /_license and /_security subsystems",
+            Self::security_subsystems(),
+        );
         output.push('\n');
 
         output.push('\n');
@@ -337,12 +611,30 @@ impl fmt::Display for ExporterOptions {
             self.exporter_skip_zero_metrics
         ));
 
+        rules_to_string(
+            &mut output,
+            "exporter_metric_type_rules",
+            &self.exporter_metric_type_rules,
+        );
+
+        relabel_configs_to_string(
+            &mut output,
+            "exporter_relabel_configs",
+            &self.exporter_relabel_configs,
+        );
+
         switch_to_string(
             &mut output,
             "exporter_metrics_enabled",
             &self.exporter_metrics_enabled,
         );
 
+        switch_to_string(
+            &mut output,
+            "exporter_sample_timestamps",
+            &self.exporter_sample_timestamps,
+        );
+
         output.push('\n');
         output.push_str(&format!(
             "exporter_metrics_namespace: {}",
@@ -367,6 +659,166 @@ impl fmt::Display for ExporterOptions {
             &self.exporter_metrics_lifetime_interval,
         );
 
+        output.push('\n');
+        output.push_str(&format!(
+            "exporter_shard_size_threshold: {}",
+            self.exporter_shard_size_threshold
+        ));
+        output.push('\n');
+        output.push_str(&format!(
+            "exporter_max_series_default: {}",
+            self.exporter_max_series_default
+        ));
+        output.push('\n');
+        output.push_str("exporter_max_series:");
+        for (k, v) in self.exporter_max_series.iter() {
+            output.push('\n');
+            output.push_str(&format!(" - {}: {}", k, v));
+        }
+
+        if let Some(ref url) = self.exporter_remote_write_url {
+            output.push('\n');
+            output.push_str(&format!("exporter_remote_write_url: {}", url));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_remote_write_interval: {:?}",
+                self.exporter_remote_write_interval
+            ));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_remote_write_timeout: {:?}",
+                self.exporter_remote_write_timeout
+            ));
+            output.push('\n');
+            // Header values are not printed as these contain credentials
+            output.push_str(&format!(
+                "exporter_remote_write_headers: {}",
+                self.exporter_remote_write_headers
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_remote_write_queue_size: {}",
+                self.exporter_remote_write_queue_size
+            ));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_remote_write_max_retries: {}",
+                self.exporter_remote_write_max_retries
+            ));
+        }
+
+        if let Some(ref url) = self.exporter_otlp_url {
+            output.push('\n');
+            output.push_str(&format!("exporter_otlp_url: {}", url));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_otlp_interval: {:?}",
+                self.exporter_otlp_interval
+            ));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_otlp_timeout: {:?}",
+                self.exporter_otlp_timeout
+            ));
+            output.push('\n');
+            // Header values are not printed as these contain credentials
+            output.push_str(&format!(
+                "exporter_otlp_headers: {}",
+                self.exporter_otlp_headers
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ));
+            if let Some(ref instance) = self.exporter_otlp_instance {
+                output.push('\n');
+                output.push_str(&format!("exporter_otlp_instance: {}", instance));
+            }
+        }
+
+        if self.exporter_on_demand {
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_on_demand_min_interval: {:?}",
+                self.exporter_on_demand_min_interval
+            ));
+        }
+
+        if let Some(ref addr) = self.exporter_statsd_addr {
+            output.push('\n');
+            output.push_str(&format!("exporter_statsd_addr: {}", addr));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_statsd_prefix: {}",
+                self.exporter_statsd_prefix
+            ));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_statsd_flush_interval: {:?}",
+                self.exporter_statsd_flush_interval
+            ));
+        }
+
+        if let Some(ref addr) = self.exporter_graphite_addr {
+            output.push('\n');
+            output.push_str(&format!("exporter_graphite_addr: {}", addr));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_graphite_prefix: {}",
+                self.exporter_graphite_prefix
+            ));
+            output.push('\n');
+            output.push_str(&format!(
+                "exporter_graphite_flush_interval: {:?}",
+                self.exporter_graphite_flush_interval
+            ));
+        }
+
+        if !self.exporter_queries.is_empty() {
+            output.push('\n');
+            output.push_str("exporter_queries:");
+            for query in self.exporter_queries.iter() {
+                output.push('\n');
+                output.push_str(&format!(
+                    " - {}: {}",
+                    query.subsystem(),
+                    query.indices().join(",")
+                ));
+            }
+        }
+
+        if !self.exporter_endpoints.is_empty() {
+            output.push('\n');
+            output.push_str("exporter_endpoints:");
+            for endpoint in self.exporter_endpoints.iter() {
+                output.push('\n');
+                output.push_str(&format!(
+                    " - {}: {:?} {}",
+                    endpoint.subsystem(),
+                    endpoint.method(),
+                    endpoint.path()
+                ));
+            }
+        }
+
+        if !self.exporter_freshness.is_empty() {
+            output.push('\n');
+            output.push_str("exporter_freshness:");
+            for group in self.exporter_freshness.iter() {
+                output.push('\n');
+                output.push_str(&format!(
+                    " - {}: {} {}",
+                    group.name(),
+                    group.indices().join(","),
+                    group.field()
+                ));
+            }
+        }
+
         output.push('\n');
         write!(f, "{}", output)
     }
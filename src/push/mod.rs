@@ -0,0 +1,148 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+use crate::collection::registry;
+use crate::{Exporter, PushHeaders};
+
+/// OpenTelemetry OTLP/HTTP metrics export
+pub(crate) mod otlp;
+/// Prometheus remote_write push mode
+pub(crate) mod remote_write;
+
+/// HTTP client with default headers, e.g.: Authorization
+fn client(timeout: Duration, headers: &PushHeaders) -> Result<reqwest::Client, String> {
+    let mut header_map = HeaderMap::new();
+
+    for (name, value) in headers.iter() {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                let _ = header_map.insert(name, value);
+            }
+            _ => return Err(format!("invalid header {}", name)),
+        }
+    }
+
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .default_headers(header_map)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug)]
+enum Retry {
+    /// Non recoverable error, e.g.: bad request
+    Drop(String),
+    /// Retries exhausted, retry on next interval
+    Later(String),
+}
+
+/// POST payload retrying with exponential backoff on server errors and 429
+async fn send(
+    client: &reqwest::Client,
+    url: &url::Url,
+    headers: &[(&'static str, &'static str)],
+    payload: &[u8],
+    max_retries: usize,
+) -> Result<(), Retry> {
+    let mut backoff = Duration::from_millis(500);
+    let mut attempt = 0;
+
+    loop {
+        let mut request = client.post(url.as_str()).body(payload.to_vec());
+
+        for (name, value) in headers.iter() {
+            request = request.header(*name, *value);
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            // 4xx other than 429 must not be retried
+            Ok(response)
+                if response.status().is_client_error()
+                    && response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS =>
+            {
+                return Err(Retry::Drop(format!("status {}", response.status())));
+            }
+            Ok(response) => format!("status {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt >= max_retries {
+            return Err(Retry::Later(error));
+        }
+
+        debug!("push {} retry {} err {}", url, attempt + 1, error);
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(Duration::from_secs(30));
+        attempt += 1;
+    }
+}
+
+/// Subsystem poll completion timestamp in milliseconds of metric family,
+/// `None` if metric is not a subsystem metric or subsystem was not polled yet
+///
+/// Subsystem is resolved from metric registry, thus user configured
+/// (`query_<name>`, `endpoint_<name>`, etc.) subsystems are resolved too
+fn subsystem_timestamp(exporter: &Exporter, name: &str) -> Option<i64> {
+    registry::subsystem(name).and_then(|subsystem| exporter.last_collect(subsystem))
+}
+
+// Protobuf wire format encoding
+// https://protobuf.dev/programming-guides/encoding/
+
+fn encode_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Wire type 0: varint
+fn encode_uint(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    encode_varint(buffer, field << 3);
+    encode_varint(buffer, value);
+}
+
+/// Wire type 1: 64-bit
+fn encode_fixed64(buffer: &mut Vec<u8>, field: u64, value: [u8; 8]) {
+    encode_varint(buffer, (field << 3) | 1);
+    buffer.extend_from_slice(&value);
+}
+
+/// Wire type 2: length delimited
+fn encode_bytes(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    encode_varint(buffer, (field << 3) | 2);
+    encode_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protobuf_encode() {
+        let mut buffer = vec![];
+        encode_varint(&mut buffer, 300);
+        assert_eq!(buffer, vec![0xac, 0x02]);
+
+        buffer.clear();
+        encode_uint(&mut buffer, 2, 150);
+        assert_eq!(buffer, vec![0x10, 0x96, 0x01]);
+
+        buffer.clear();
+        encode_bytes(&mut buffer, 1, b"testing");
+        assert_eq!(buffer, b"\x0a\x07testing");
+
+        buffer.clear();
+        encode_fixed64(&mut buffer, 4, 1.0f64.to_le_bytes());
+        assert_eq!(buffer[0], 0x21);
+        assert_eq!(buffer.len(), 9);
+    }
+}
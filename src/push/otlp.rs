@@ -0,0 +1,400 @@
+use prometheus::proto::{self, MetricFamily};
+
+use super::{encode_bytes, encode_fixed64, encode_uint, send, subsystem_timestamp, Retry};
+use crate::collection::created;
+use crate::metric::catalog;
+use crate::Exporter;
+
+const HEADERS: &[(&str, &str)] = &[
+    ("Content-Type", "application/x-protobuf"),
+    ("Content-Encoding", "gzip"),
+];
+
+/// Retries of a payload before it is dropped, next interval sends fresh
+/// cumulative values thus failed payloads are not queued
+const MAX_RETRIES: usize = 3;
+
+/// Instrumentation scope name and `service.name` resource attribute
+const SCOPE_NAME: &str = "elasticsearch_exporter";
+
+/// AggregationTemporality AGGREGATION_TEMPORALITY_CUMULATIVE
+const CUMULATIVE: u64 = 2;
+
+/// Resource attributes of exported metrics
+#[derive(Debug)]
+struct Resource {
+    cluster_name: String,
+    instance: String,
+}
+
+/// Push registry as OTLP/HTTP protobuf metrics on configured interval
+///
+/// https://opentelemetry.io/docs/specs/otlp/#otlphttp
+pub(crate) async fn push(exporter: Exporter) {
+    let options = exporter.options();
+
+    let Some(url) = options.exporter_otlp_url.clone() else {
+        return;
+    };
+
+    let client = match super::client(
+        options.exporter_otlp_timeout,
+        &options.exporter_otlp_headers,
+    ) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("otlp client err {}", e);
+            return;
+        }
+    };
+
+    let resource = Resource {
+        cluster_name: exporter.cluster_name().to_string(),
+        instance: options
+            .exporter_otlp_instance
+            .clone()
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .unwrap_or_else(|| "localhost".into()),
+    };
+
+    info!(
+        "Starting OTLP export to: {} with interval: {}sec",
+        url,
+        options.exporter_otlp_interval.as_secs()
+    );
+
+    let mut interval = tokio::time::interval(options.exporter_otlp_interval);
+
+    loop {
+        let _ = interval.tick().await;
+
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let request = encode(&resource, &prometheus::gather(), |name| {
+            subsystem_timestamp(&exporter, name).unwrap_or(now)
+        });
+
+        let payload = match crate::exposition::gzip(&request) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("otlp gzip err {}", e);
+                continue;
+            }
+        };
+
+        match send(&client, &url, HEADERS, &payload, MAX_RETRIES).await {
+            Ok(()) => {}
+            Err(Retry::Drop(e)) | Err(Retry::Later(e)) => {
+                error!("otlp dropping payload err {}", e);
+            }
+        }
+    }
+}
+
+/// Unified Code for Units of Measure used by OTLP
+fn ucum(unit: &str) -> &str {
+    match unit {
+        "seconds" => "s",
+        "milliseconds" => "ms",
+        "bytes" => "By",
+        "percent" => "%",
+        "ratio" => "1",
+        unit => unit,
+    }
+}
+
+fn millis_to_nanos(timestamp_ms: i64) -> [u8; 8] {
+    ((timestamp_ms.max(0) as u64) * 1_000_000).to_le_bytes()
+}
+
+// Protobuf encoding of opentelemetry.proto.collector.metrics.v1.ExportMetricsServiceRequest
+//
+// message ExportMetricsServiceRequest { repeated ResourceMetrics resource_metrics = 1; }
+// message ResourceMetrics { Resource resource = 1; repeated ScopeMetrics scope_metrics = 2; }
+// message Resource { repeated KeyValue attributes = 1; }
+// message ScopeMetrics { InstrumentationScope scope = 1; repeated Metric metrics = 2; }
+// message InstrumentationScope { string name = 1; string version = 2; }
+// message KeyValue { string key = 1; AnyValue value = 2; }
+// message AnyValue { string string_value = 1; }
+// message Metric { string name = 1; string description = 2; string unit = 3;
+//   Gauge gauge = 5; Sum sum = 7; Histogram histogram = 9; Summary summary = 11; }
+
+/// Encode metric families as ExportMetricsServiceRequest, `timestamp`
+/// returns sample timestamp in milliseconds by metric family name
+fn encode(
+    resource: &Resource,
+    families: &[MetricFamily],
+    timestamp: impl Fn(&str) -> i64,
+) -> Vec<u8> {
+    let mut attributes = Vec::new();
+    for (key, value) in [
+        ("service.name", SCOPE_NAME),
+        ("service.instance.id", resource.instance.as_str()),
+        ("elasticsearch.cluster.name", resource.cluster_name.as_str()),
+    ] {
+        encode_key_value(&mut attributes, 1, key, value);
+    }
+
+    let mut scope = Vec::new();
+    encode_bytes(&mut scope, 1, SCOPE_NAME.as_bytes());
+    encode_bytes(&mut scope, 2, env!("CARGO_PKG_VERSION").as_bytes());
+
+    let mut scope_metrics = Vec::new();
+    encode_bytes(&mut scope_metrics, 1, &scope);
+
+    let mut metric = Vec::new();
+    let mut data = Vec::new();
+    for family in families.iter() {
+        let name = family.get_name();
+        let family_timestamp = timestamp(name);
+
+        data.clear();
+        let data_field = match family.get_field_type() {
+            proto::MetricType::GAUGE | proto::MetricType::UNTYPED => {
+                encode_data_points(&mut data, family, family_timestamp);
+                5
+            }
+            proto::MetricType::COUNTER => {
+                encode_data_points(&mut data, family, family_timestamp);
+                encode_uint(&mut data, 2, CUMULATIVE);
+                encode_uint(&mut data, 3, 1);
+                7
+            }
+            proto::MetricType::HISTOGRAM => {
+                encode_data_points(&mut data, family, family_timestamp);
+                encode_uint(&mut data, 2, CUMULATIVE);
+                9
+            }
+            proto::MetricType::SUMMARY => {
+                encode_data_points(&mut data, family, family_timestamp);
+                11
+            }
+        };
+
+        metric.clear();
+        encode_bytes(&mut metric, 1, name.as_bytes());
+        encode_bytes(&mut metric, 2, family.get_help().as_bytes());
+        if let Some(unit) = catalog::unit(name) {
+            encode_bytes(&mut metric, 3, ucum(unit).as_bytes());
+        }
+        encode_bytes(&mut metric, data_field, &data);
+
+        encode_bytes(&mut scope_metrics, 2, &metric);
+    }
+
+    let mut resource = Vec::new();
+    encode_bytes(&mut resource, 1, &attributes);
+
+    let mut resource_metrics = Vec::new();
+    encode_bytes(&mut resource_metrics, 1, &resource);
+    encode_bytes(&mut resource_metrics, 2, &scope_metrics);
+
+    let mut buffer = Vec::new();
+    encode_bytes(&mut buffer, 1, &resource_metrics);
+    buffer
+}
+
+fn encode_key_value(buffer: &mut Vec<u8>, field: u64, key: &str, value: &str) {
+    let mut any_value = Vec::new();
+    encode_bytes(&mut any_value, 1, value.as_bytes());
+
+    let mut key_value = Vec::new();
+    encode_bytes(&mut key_value, 1, key.as_bytes());
+    encode_bytes(&mut key_value, 2, &any_value);
+
+    encode_bytes(buffer, field, &key_value);
+}
+
+// message NumberDataPoint { repeated KeyValue attributes = 7; fixed64 start_time_unix_nano = 2;
+//   fixed64 time_unix_nano = 3; double as_double = 4; }
+// message HistogramDataPoint { repeated KeyValue attributes = 9; fixed64 start_time_unix_nano = 2;
+//   fixed64 time_unix_nano = 3; fixed64 count = 4; double sum = 5;
+//   repeated fixed64 bucket_counts = 6; repeated double explicit_bounds = 7; }
+// message SummaryDataPoint { repeated KeyValue attributes = 7; fixed64 start_time_unix_nano = 2;
+//   fixed64 time_unix_nano = 3; fixed64 count = 4; double sum = 5;
+//   repeated ValueAtQuantile quantile_values = 6; }
+// message ValueAtQuantile { double quantile = 1; double value = 2; }
+
+/// Encode data points (field 1) of Gauge, Sum, Histogram or Summary
+fn encode_data_points(buffer: &mut Vec<u8>, family: &MetricFamily, family_timestamp: i64) {
+    let name = family.get_name();
+    let mut point = Vec::new();
+
+    for metric in family.get_metric().iter() {
+        point.clear();
+
+        let timestamp_ms = match metric.get_timestamp_ms() {
+            0 => family_timestamp,
+            timestamp_ms => timestamp_ms,
+        };
+
+        let attributes_field = match family.get_field_type() {
+            proto::MetricType::HISTOGRAM => 9,
+            _ => 7,
+        };
+        for label in metric.get_label().iter() {
+            encode_key_value(
+                &mut point,
+                attributes_field,
+                label.get_name(),
+                label.get_value(),
+            );
+        }
+
+        // Labels are ordered by name, the same order created timestamps are
+        // keyed by
+        let label_values = metric
+            .get_label()
+            .iter()
+            .map(|label| label.get_value())
+            .collect::<Vec<&str>>();
+        if let Some(created) = created::get(name, &label_values) {
+            encode_fixed64(&mut point, 2, millis_to_nanos((created * 1000.0) as i64));
+        }
+
+        encode_fixed64(&mut point, 3, millis_to_nanos(timestamp_ms));
+
+        match family.get_field_type() {
+            proto::MetricType::GAUGE => {
+                encode_fixed64(&mut point, 4, metric.get_gauge().get_value().to_le_bytes())
+            }
+            proto::MetricType::UNTYPED => encode_fixed64(
+                &mut point,
+                4,
+                metric.get_untyped().get_value().to_le_bytes(),
+            ),
+            proto::MetricType::COUNTER => encode_fixed64(
+                &mut point,
+                4,
+                metric.get_counter().get_value().to_le_bytes(),
+            ),
+            proto::MetricType::HISTOGRAM => {
+                let histogram = metric.get_histogram();
+
+                encode_fixed64(&mut point, 4, histogram.get_sample_count().to_le_bytes());
+                encode_fixed64(&mut point, 5, histogram.get_sample_sum().to_le_bytes());
+
+                // OTLP bucket counts are not cumulative and have implicit
+                // +Inf bucket, i.e.: one more count than explicit bounds
+                let mut bounds = Vec::new();
+                let mut counts = Vec::new();
+                let mut previous = 0;
+                for bucket in histogram.get_bucket().iter() {
+                    if bucket.get_upper_bound() == f64::INFINITY {
+                        continue;
+                    }
+                    bounds.extend_from_slice(&bucket.get_upper_bound().to_le_bytes());
+                    counts.extend_from_slice(
+                        &(bucket.get_cumulative_count().saturating_sub(previous)).to_le_bytes(),
+                    );
+                    previous = bucket.get_cumulative_count();
+                }
+                counts.extend_from_slice(
+                    &histogram
+                        .get_sample_count()
+                        .saturating_sub(previous)
+                        .to_le_bytes(),
+                );
+
+                // Packed repeated fields
+                encode_bytes(&mut point, 6, &counts);
+                encode_bytes(&mut point, 7, &bounds);
+            }
+            proto::MetricType::SUMMARY => {
+                let summary = metric.get_summary();
+
+                encode_fixed64(&mut point, 4, summary.get_sample_count().to_le_bytes());
+                encode_fixed64(&mut point, 5, summary.get_sample_sum().to_le_bytes());
+
+                let mut quantile = Vec::new();
+                for value in summary.get_quantile().iter() {
+                    quantile.clear();
+                    encode_fixed64(&mut quantile, 1, value.get_quantile().to_le_bytes());
+                    encode_fixed64(&mut quantile, 2, value.get_value().to_le_bytes());
+                    encode_bytes(&mut point, 6, &quantile);
+                }
+            }
+        }
+
+        encode_bytes(buffer, 1, &point);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{GaugeVec, HistogramOpts, HistogramVec, Opts, Registry};
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn test_otlp_encode() {
+        let registry = Registry::new();
+
+        let gauge = GaugeVec::new(
+            Opts::new("cat_health_shards", "cat_health_shards"),
+            &["cluster"],
+        )
+        .unwrap();
+        gauge.with_label_values(&["testing"]).set(5.0);
+        registry.register(Box::new(gauge)).unwrap();
+
+        let histogram = HistogramVec::new(
+            HistogramOpts::new("request_seconds", "request_seconds").buckets(vec![0.1, 1.0]),
+            &["subsystem"],
+        )
+        .unwrap();
+        histogram.with_label_values(&["cat_health"]).observe(0.5);
+        histogram.with_label_values(&["cat_health"]).observe(5.0);
+        registry.register(Box::new(histogram)).unwrap();
+
+        let resource = Resource {
+            cluster_name: "testing".into(),
+            instance: "exporter-0".into(),
+        };
+
+        let encoded = encode(&resource, &registry.gather(), |_| 1700000000000);
+
+        // ExportMetricsServiceRequest.resource_metrics
+        assert_eq!(encoded[0], 0x0a);
+
+        let mut attribute = vec![];
+        encode_key_value(&mut attribute, 1, "elasticsearch.cluster.name", "testing");
+        assert!(contains(&encoded, &attribute));
+
+        attribute.clear();
+        encode_key_value(&mut attribute, 1, "service.instance.id", "exporter-0");
+        assert!(contains(&encoded, &attribute));
+
+        // Gauge data point: attributes, time_unix_nano and as_double
+        let mut point = vec![];
+        encode_key_value(&mut point, 7, "cluster", "testing");
+        encode_fixed64(&mut point, 3, millis_to_nanos(1700000000000));
+        encode_fixed64(&mut point, 4, 5.0f64.to_le_bytes());
+        let mut gauge = vec![];
+        encode_bytes(&mut gauge, 1, &point);
+        let mut metric = vec![];
+        encode_bytes(&mut metric, 5, &gauge);
+        assert!(contains(&encoded, &metric));
+
+        // Histogram non cumulative bucket counts with implicit +Inf bucket
+        let mut counts = vec![];
+        for count in [0u64, 1, 1] {
+            counts.extend_from_slice(&count.to_le_bytes());
+        }
+        let mut bucket_counts = vec![];
+        encode_bytes(&mut bucket_counts, 6, &counts);
+        assert!(contains(&encoded, &bucket_counts));
+    }
+
+    #[test]
+    fn test_otlp_ucum() {
+        assert_eq!(ucum("seconds"), "s");
+        assert_eq!(ucum("bytes"), "By");
+        assert_eq!(ucum("requests"), "requests");
+    }
+}
@@ -0,0 +1,287 @@
+use prometheus::proto::{self, MetricFamily};
+use std::collections::VecDeque;
+
+use super::{encode_bytes, encode_fixed64, encode_uint, send, subsystem_timestamp, Retry};
+use crate::Exporter;
+
+const HEADERS: &[(&str, &str)] = &[
+    ("Content-Type", "application/x-protobuf"),
+    ("Content-Encoding", "snappy"),
+    ("X-Prometheus-Remote-Write-Version", "0.1.0"),
+];
+
+/// Single sample time series of remote write request
+#[derive(Debug, PartialEq)]
+struct TimeSeries {
+    /// Labels including `__name__` sorted by name
+    labels: Vec<(String, String)>,
+    value: f64,
+    timestamp_ms: i64,
+}
+
+/// Push registry via Prometheus remote_write on configured interval
+///
+/// https://prometheus.io/docs/concepts/remote_write_spec/
+pub(crate) async fn push(exporter: Exporter) {
+    let options = exporter.options();
+
+    let Some(url) = options.exporter_remote_write_url.clone() else {
+        return;
+    };
+
+    let client = match super::client(
+        options.exporter_remote_write_timeout,
+        &options.exporter_remote_write_headers,
+    ) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("remote_write client err {}", e);
+            return;
+        }
+    };
+
+    let mut queue: VecDeque<Vec<u8>> =
+        VecDeque::with_capacity(options.exporter_remote_write_queue_size);
+
+    info!(
+        "Starting remote_write to: {} with interval: {}sec",
+        url,
+        options.exporter_remote_write_interval.as_secs()
+    );
+
+    let mut interval = tokio::time::interval(options.exporter_remote_write_interval);
+
+    loop {
+        let _ = interval.tick().await;
+
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let series = from_families(&prometheus::gather(), |name| {
+            subsystem_timestamp(&exporter, name).unwrap_or(now)
+        });
+
+        match snap::raw::Encoder::new().compress_vec(&encode(&series)) {
+            Ok(payload) => {
+                // Bounded queue, oldest payload is dropped first
+                if queue.len() >= options.exporter_remote_write_queue_size.max(1) {
+                    let _ = queue.pop_front();
+                    warn!("remote_write queue is full, dropping oldest payload");
+                }
+                queue.push_back(payload);
+            }
+            Err(e) => error!("remote_write snappy err {}", e),
+        }
+
+        while let Some(payload) = queue.front() {
+            match send(
+                &client,
+                &url,
+                HEADERS,
+                payload,
+                options.exporter_remote_write_max_retries,
+            )
+            .await
+            {
+                Ok(()) => {
+                    let _ = queue.pop_front();
+                }
+                Err(Retry::Drop(e)) => {
+                    error!("remote_write dropping payload err {}", e);
+                    let _ = queue.pop_front();
+                }
+                Err(Retry::Later(e)) => {
+                    warn!(
+                        "remote_write failed, {} payloads queued err {}",
+                        queue.len(),
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Flatten metric families into single sample time series, `timestamp`
+/// returns sample timestamp in milliseconds by metric family name
+fn from_families(families: &[MetricFamily], timestamp: impl Fn(&str) -> i64) -> Vec<TimeSeries> {
+    let mut output = Vec::new();
+
+    for family in families.iter() {
+        let name = family.get_name();
+        let family_timestamp = timestamp(name);
+
+        for metric in family.get_metric().iter() {
+            let timestamp_ms = match metric.get_timestamp_ms() {
+                0 => family_timestamp,
+                timestamp_ms => timestamp_ms,
+            };
+
+            let mut push = |postfix: &str, extra: Option<(&str, f64)>, value: f64| {
+                let mut labels = metric
+                    .get_label()
+                    .iter()
+                    .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+                    .collect::<Vec<(String, String)>>();
+
+                labels.push(("__name__".into(), format!("{}{}", name, postfix)));
+
+                if let Some((label, value)) = extra {
+                    labels.push((label.into(), format_float(value)));
+                }
+
+                labels.sort();
+
+                output.push(TimeSeries {
+                    labels,
+                    value,
+                    timestamp_ms,
+                });
+            };
+
+            match family.get_field_type() {
+                proto::MetricType::COUNTER => push("", None, metric.get_counter().get_value()),
+                proto::MetricType::GAUGE => push("", None, metric.get_gauge().get_value()),
+                proto::MetricType::UNTYPED => push("", None, metric.get_untyped().get_value()),
+                proto::MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+
+                    for bucket in histogram.get_bucket().iter() {
+                        push(
+                            "_bucket",
+                            Some(("le", bucket.get_upper_bound())),
+                            bucket.get_cumulative_count() as f64,
+                        );
+                    }
+                    if !histogram
+                        .get_bucket()
+                        .iter()
+                        .any(|bucket| bucket.get_upper_bound() == f64::INFINITY)
+                    {
+                        push(
+                            "_bucket",
+                            Some(("le", f64::INFINITY)),
+                            histogram.get_sample_count() as f64,
+                        );
+                    }
+                    push("_count", None, histogram.get_sample_count() as f64);
+                    push("_sum", None, histogram.get_sample_sum());
+                }
+                proto::MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+
+                    for quantile in summary.get_quantile().iter() {
+                        push(
+                            "",
+                            Some(("quantile", quantile.get_quantile())),
+                            quantile.get_value(),
+                        );
+                    }
+                    push("_count", None, summary.get_sample_count() as f64);
+                    push("_sum", None, summary.get_sample_sum());
+                }
+            }
+        }
+    }
+
+    output
+}
+
+fn format_float(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".into()
+    } else {
+        value.to_string()
+    }
+}
+
+// Protobuf encoding of prometheus.WriteRequest
+//
+// message WriteRequest { repeated TimeSeries timeseries = 1; }
+// message TimeSeries { repeated Label labels = 1; repeated Sample samples = 2; }
+// message Label { string name = 1; string value = 2; }
+// message Sample { double value = 1; int64 timestamp = 2; }
+
+fn encode(series: &[TimeSeries]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let mut time_series = Vec::new();
+    let mut message = Vec::new();
+
+    for ts in series.iter() {
+        time_series.clear();
+
+        for (name, value) in ts.labels.iter() {
+            message.clear();
+            encode_bytes(&mut message, 1, name.as_bytes());
+            encode_bytes(&mut message, 2, value.as_bytes());
+            encode_bytes(&mut time_series, 1, &message);
+        }
+
+        message.clear();
+        encode_fixed64(&mut message, 1, ts.value.to_le_bytes());
+        encode_uint(&mut message, 2, ts.timestamp_ms as u64);
+        encode_bytes(&mut time_series, 2, &message);
+
+        encode_bytes(&mut buffer, 1, &time_series);
+    }
+
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::encode_varint;
+    use super::*;
+    use prometheus::{IntGaugeVec, Opts, Registry};
+
+    #[test]
+    fn test_remote_write_encode() {
+        let registry = Registry::new();
+
+        let gauge = IntGaugeVec::new(
+            Opts::new("docs_count", "docs_count")
+                .namespace("elasticsearch")
+                .subsystem("cat_indices"),
+            &["index"],
+        )
+        .unwrap();
+        gauge.with_label_values(&["logs"]).set(3);
+        registry.register(Box::new(gauge)).unwrap();
+
+        let series = from_families(&registry.gather(), |name| {
+            assert_eq!(name, "elasticsearch_cat_indices_docs_count");
+            1700000000000
+        });
+
+        assert_eq!(
+            series,
+            vec![TimeSeries {
+                labels: vec![
+                    (
+                        "__name__".into(),
+                        "elasticsearch_cat_indices_docs_count".into()
+                    ),
+                    ("index".into(), "logs".into()),
+                ],
+                value: 3.0,
+                timestamp_ms: 1700000000000,
+            }]
+        );
+
+        let encoded = encode(&series);
+        // WriteRequest.timeseries field 1, length delimited
+        assert_eq!(&encoded[..2], &[0x0a, 83]);
+        // TimeSeries.labels field 1, Label.name field 1 "__name__"
+        assert_eq!(&encoded[2..6], &[0x0a, 0x30, 0x0a, 0x08]);
+        assert_eq!(&encoded[6..14], b"__name__");
+
+        // Sample: value 3.0 and timestamp varint
+        let mut sample = vec![0x09];
+        sample.extend_from_slice(&3.0f64.to_le_bytes());
+        sample.push(0x10);
+        encode_varint(&mut sample, 1700000000000);
+        assert!(encoded.ends_with(&sample));
+
+        assert!(encode(&[]).is_empty());
+    }
+}
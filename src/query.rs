@@ -0,0 +1,411 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::config;
+use crate::metric::MetricRule;
+
+/// Subsystem name prefix of custom queries, e.g.: query_service_docs
+pub const SUBSYSTEM_PREFIX: &str = "query_";
+
+/// Aggregation types returning `buckets`, bucket key of these is a label
+const MULTI_BUCKET_AGGREGATIONS: &[&str] = &[
+    "adjacency_matrix",
+    "auto_date_histogram",
+    "composite",
+    "date_histogram",
+    "date_range",
+    "filters",
+    "geohash_grid",
+    "geotile_grid",
+    "histogram",
+    "ip_prefix",
+    "ip_range",
+    "multi_terms",
+    "range",
+    "rare_terms",
+    "significant_terms",
+    "significant_text",
+    "terms",
+    "variable_width_histogram",
+];
+
+/// JSON object of search response
+type Object = Map<String, Value>;
+
+/// Bucket fields which are not sub-aggregations
+const BUCKET_FIELDS: &[&str] = &["key", "key_as_string", "doc_count", "from", "to"];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawQueryConfig {
+    name: String,
+    index: String,
+    #[serde(default)]
+    query: Option<Value>,
+    #[serde(default, alias = "aggs")]
+    aggregations: Option<Object>,
+    #[serde(default)]
+    mapping: BTreeMap<String, String>,
+}
+
+/// Custom search query polled as subsystem `query_<name>`, e.g.:
+///
+/// {"name": "service_docs", "index": "logs-*", "query": {"range": {"@timestamp": {"gte": "now-5m"}}},
+///  "aggs": {"service": {"terms": {"field": "service"}}}, "mapping": {"service": "service_name"}}
+///
+/// `hits.total` is exported as `hits` gauge, bucket keys of multi bucket
+/// aggregations become labels and metric aggregation values become metrics
+/// named by aggregation, `mapping` renames aggregation to label or metric name
+#[derive(Debug, Clone)]
+pub struct QueryConfig {
+    subsystem: &'static str,
+    indices: Vec<String>,
+    body: Value,
+    mapping: BTreeMap<String, String>,
+    labels: Vec<String>,
+    rules: Vec<MetricRule>,
+}
+
+impl TryFrom<RawQueryConfig> for QueryConfig {
+    type Error = String;
+
+    fn try_from(raw: RawQueryConfig) -> Result<Self, Self::Error> {
+        let subsystem = config::subsystem_name(SUBSYSTEM_PREFIX, "query", &raw.name)?;
+
+        let indices = raw
+            .index
+            .split(',')
+            .map(str::trim)
+            .filter(|index| !index.is_empty())
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        if indices.is_empty() {
+            return Err(format!("query `{}` requires `index`", raw.name));
+        }
+
+        let mut body = json!({
+            "size": 0,
+            "track_total_hits": true,
+            "query": raw.query.unwrap_or_else(|| json!({"match_all": {}})),
+        });
+
+        let mut labels = Vec::new();
+
+        if let Some(aggregations) = raw.aggregations {
+            bucket_labels(&aggregations, &raw.mapping, &mut labels);
+            body["aggs"] = Value::Object(aggregations);
+        }
+
+        labels.sort();
+        labels.dedup();
+
+        let rules = labels
+            .iter()
+            .map(|label| format!("{}:label", label).parse::<MetricRule>())
+            .collect::<Result<Vec<MetricRule>, _>>()
+            .map_err(|e| format!("query `{}` label rule err {}", raw.name, e))?;
+
+        Ok(Self {
+            subsystem,
+            indices,
+            body,
+            mapping: raw.mapping,
+            labels,
+            rules,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for QueryConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        config::deserialize_try_from::<D, RawQueryConfig, Self>(deserializer)
+    }
+}
+
+/// Label names of multi bucket aggregations of request aggregations tree
+fn bucket_labels(
+    aggregations: &Object,
+    mapping: &BTreeMap<String, String>,
+    labels: &mut Vec<String>,
+) {
+    for (name, aggregation) in aggregations.iter() {
+        let Some(aggregation) = aggregation.as_object() else {
+            continue;
+        };
+
+        for (kind, body) in aggregation.iter() {
+            match kind.as_str() {
+                "aggs" | "aggregations" => {
+                    if let Some(sub) = body.as_object() {
+                        bucket_labels(sub, mapping, labels);
+                    }
+                }
+                // Composite bucket key is an object keyed by source name
+                "composite" => labels.extend(
+                    body.get("sources")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_object)
+                        .flat_map(|source| source.keys())
+                        .map(|source| label_name(source)),
+                ),
+                kind if MULTI_BUCKET_AGGREGATIONS.contains(&kind) => {
+                    labels.push(label_name(mapping.get(name).unwrap_or(name)))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Prometheus label name, characters other than [a-zA-Z0-9_] are replaced
+fn label_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+impl QueryConfig {
+    /// Subsystem name, e.g.: query_service_docs
+    pub fn subsystem(&self) -> &'static str {
+        self.subsystem
+    }
+
+    /// Searched indices or index patterns
+    pub fn indices(&self) -> Vec<&str> {
+        self.indices.iter().map(String::as_str).collect()
+    }
+
+    /// Search request body
+    pub fn body(&self) -> &Value {
+        &self.body
+    }
+
+    /// Label names of bucket keys
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Metric type rules keeping bucket keys as labels, e.g.: numeric
+    /// histogram keys
+    pub fn rules(&self) -> &[MetricRule] {
+        &self.rules
+    }
+
+    /// Flat metric values of search response, first value holds `hits` and
+    /// top level metric aggregations, each bucket is a value of its own
+    /// holding labels of all parent bucket keys
+    pub fn values(&self, response: &Value) -> Vec<Value> {
+        let mut root = Map::new();
+        let mut values = Vec::new();
+
+        // `hits.total` is an object since Elasticsearch 7
+        let total = match response.pointer("/hits/total") {
+            Some(Value::Object(total)) => total.get("value").cloned(),
+            Some(total) => Some(total.clone()),
+            None => None,
+        };
+
+        if let Some(total) = total {
+            let _ = root.insert("hits".into(), total);
+        }
+
+        if let Some(aggregations) = response.get("aggregations").and_then(Value::as_object) {
+            self.aggregations(aggregations, &Map::new(), &mut root, &mut values);
+        }
+
+        values.insert(0, Value::Object(root));
+        values
+    }
+
+    fn name<'a>(&'a self, aggregation: &'a str) -> &'a str {
+        self.mapping
+            .get(aggregation)
+            .map(String::as_str)
+            .unwrap_or(aggregation)
+    }
+
+    fn aggregations(
+        &self,
+        aggregations: &Object,
+        labels: &Object,
+        value: &mut Object,
+        values: &mut Vec<Value>,
+    ) {
+        for (name, aggregation) in aggregations.iter() {
+            if BUCKET_FIELDS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let Some(aggregation) = aggregation.as_object() else {
+                continue;
+            };
+
+            let key = self.name(name);
+            // Mapped name may contain dots, e.g.: "service.name"
+            let metric_key = label_name(key);
+
+            if let Some(buckets) = aggregation.get("buckets") {
+                let buckets: Vec<(Object, &Object)> = match buckets {
+                    Value::Array(buckets) => buckets
+                        .iter()
+                        .filter_map(Value::as_object)
+                        .map(|bucket| (bucket_key(key, bucket), bucket))
+                        .collect(),
+                    // Keyed buckets, e.g.: filters
+                    Value::Object(buckets) => buckets
+                        .iter()
+                        .filter_map(|(bucket_key, bucket)| Some((bucket_key, bucket.as_object()?)))
+                        .map(|(bucket_key, bucket)| {
+                            let mut keys = Map::new();
+                            let _ = keys.insert(label_name(key), bucket_key.as_str().into());
+                            (keys, bucket)
+                        })
+                        .collect(),
+                    _ => continue,
+                };
+
+                for (keys, bucket) in buckets.into_iter() {
+                    let mut labels = labels.clone();
+                    labels.extend(keys);
+
+                    let mut bucket_value = labels.clone();
+
+                    if let Some(doc_count) = bucket.get("doc_count") {
+                        let _ = bucket_value
+                            .insert(format!("{}_doc_count", metric_key), doc_count.clone());
+                    }
+
+                    self.aggregations(bucket, &labels, &mut bucket_value, values);
+                    values.push(Value::Object(bucket_value));
+                }
+            } else if let Some(metric) = aggregation.get("value") {
+                // Single value metric aggregation, e.g.: max, cardinality
+                if !metric.is_null() {
+                    let _ = value.insert(metric_key, metric.clone());
+                }
+            } else if let Some(doc_count) = aggregation.get("doc_count") {
+                // Single bucket aggregation, e.g.: filter, nested
+                let _ = value.insert(format!("{}_doc_count", metric_key), doc_count.clone());
+                self.aggregations(aggregation, labels, value, values);
+            } else {
+                // Multi value metric aggregation, e.g.: stats, percentiles
+                let _ = value.insert(metric_key, Value::Object(aggregation.clone()));
+            }
+        }
+    }
+}
+
+/// Labels of bucket key, composite key has label per source
+fn bucket_key(name: &str, bucket: &Object) -> Object {
+    let mut keys = Map::new();
+
+    let key = bucket.get("key_as_string").or_else(|| bucket.get("key"));
+
+    match key {
+        Some(Value::Object(sources)) => {
+            for (source, key) in sources.iter() {
+                let _ = keys.insert(label_name(source), label_value(key).into());
+            }
+        }
+        Some(key) => {
+            let _ = keys.insert(label_name(name), label_value(key).into());
+        }
+        None => {}
+    }
+
+    keys
+}
+
+fn label_value(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(label_value)
+            .collect::<Vec<String>>()
+            .join(","),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_config() {
+        let config: QueryConfig = serde_json::from_str(
+            r#"{
+                "name": "service_docs",
+                "index": "logs-*, metrics-*",
+                "aggs": {
+                    "service": {
+                        "terms": {"field": "service.name"},
+                        "aggs": {
+                            "status": {"histogram": {"field": "status", "interval": 100}},
+                            "latest": {"max": {"field": "@timestamp"}}
+                        }
+                    }
+                },
+                "mapping": {"service": "service.name"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.subsystem(), "query_service_docs");
+        assert_eq!(config.indices(), vec!["logs-*", "metrics-*"]);
+        assert_eq!(config.labels(), &["service_name", "status"]);
+        assert_eq!(config.body()["size"], 0);
+        assert_eq!(config.body()["query"], json!({"match_all": {}}));
+
+        assert!(
+            serde_json::from_str::<QueryConfig>(r#"{"name": "Bad-Name", "index": "logs"}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_query_values() {
+        let config: QueryConfig = serde_json::from_str(
+            r#"{
+                "name": "docs",
+                "index": "logs-*",
+                "aggs": {
+                    "service": {
+                        "terms": {"field": "service"},
+                        "aggs": {"status": {"histogram": {"field": "status", "interval": 100}}}
+                    }
+                },
+                "mapping": {"latest": "latest_timestamp_millis", "service": "service.name"}
+            }"#,
+        )
+        .unwrap();
+
+        let values = config
+            .values(&serde_json::from_str(include_str!("tests/files/query_search.json")).unwrap());
+
+        assert_eq!(
+            values,
+            vec![
+                json!({"hits": 1250, "errors_doc_count": 12, "latest_timestamp_millis": 1700000000000.0}),
+                json!({"service_name": "api", "status": "200.0", "status_doc_count": 690}),
+                json!({"service_name": "api", "status": "500.0", "status_doc_count": 10}),
+                json!({"service_name": "api", "service_name_doc_count": 700, "bytes": {"count": 700, "min": 10.0, "max": 900.0}}),
+                json!({"service_name": "web", "service_name_doc_count": 550}),
+            ]
+        );
+
+        // Numeric bucket keys are kept as labels
+        let metrics = crate::metric::from_values_with_rules(values, config.rules());
+        assert!(metrics[1].iter().any(|metric| metric.key() == "status"
+            && *metric.metric_type() == crate::metric::MetricType::Label("200.0".into())));
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{sanitize, Sample, Sink, Transport};
+
+/// Characters reserved by Graphite metric path
+const RESERVED: &[char] = &['.', '/', ';', '='];
+
+/// Maximum buffered lines, oldest lines are dropped when Graphite is
+/// unreachable for longer periods
+const MAX_BUFFERED_LINES: usize = 1_000_000;
+
+/// Graphite plaintext protocol with labels folded into dotted path
+///
+/// https://graphite.readthedocs.io/en/latest/feeding-carbon.html#the-plaintext-protocol
+#[derive(Debug)]
+pub struct GraphiteSink {
+    transport: Transport,
+    prefix: String,
+    flush_interval: Duration,
+    buffer: Mutex<VecDeque<String>>,
+}
+
+impl GraphiteSink {
+    /// New Graphite sink sending to TCP address, e.g.: 127.0.0.1:2003
+    pub fn new(addr: String, prefix: String, flush_interval: Duration) -> Self {
+        Self {
+            transport: Transport::Tcp(addr),
+            prefix,
+            flush_interval,
+            buffer: Default::default(),
+        }
+    }
+
+    /// Metric path of sample with labels folded as `label.value` path
+    /// segments ordered by label name, e.g.:
+    /// `elasticsearch.cat_indices.cluster.testing.index.logs.docs_count`
+    fn path(&self, sample: &Sample<'_>) -> String {
+        let mut path = String::new();
+
+        if !self.prefix.is_empty() {
+            path.push_str(&self.prefix);
+            path.push('.');
+        }
+        path.push_str(sample.subsystem);
+
+        for (name, value) in sample.labels.iter() {
+            path.push('.');
+            path.push_str(&sanitize(name, RESERVED));
+            path.push('.');
+            path.push_str(&sanitize(value, RESERVED));
+        }
+
+        path.push('.');
+        path.push_str(&sanitize(sample.key, RESERVED));
+
+        path
+    }
+}
+
+impl Sink for GraphiteSink {
+    fn name(&self) -> &'static str {
+        "graphite"
+    }
+
+    fn push(&self, sample: &Sample<'_>) {
+        if !sample.value.is_finite() {
+            return;
+        }
+
+        let line = format!(
+            "{} {} {}\n",
+            self.path(sample),
+            sample.value,
+            sample.timestamp
+        );
+
+        let mut buffer = self.buffer.lock().expect("graphite buffer lock");
+
+        if buffer.len() >= MAX_BUFFERED_LINES {
+            let _ = buffer.pop_front();
+        }
+
+        buffer.push_back(line);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.buffer.lock().expect("graphite buffer lock")).into()
+    }
+
+    fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    fn flush_interval(&self) -> Duration {
+        self.flush_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Labels;
+
+    #[test]
+    fn test_graphite_sink() {
+        let sink = GraphiteSink::new(
+            "127.0.0.1:2003".into(),
+            "elasticsearch".into(),
+            Duration::from_secs(60),
+        );
+
+        let mut labels = Labels::new();
+        let _ = labels.insert("index".into(), "logs.2024/01".into());
+        let _ = labels.insert("cluster".into(), "testing".into());
+
+        sink.push(&Sample {
+            subsystem: "cat_indices",
+            key: "docs_count",
+            labels: &labels,
+            value: 2.5,
+            timestamp: 1700000000,
+        });
+
+        assert_eq!(
+            sink.take(),
+            vec![
+                "elasticsearch.cat_indices.cluster.testing.index.logs_2024_01.docs_count 2.5 1700000000\n"
+                    .to_string()
+            ]
+        );
+        assert!(sink.take().is_empty());
+    }
+}
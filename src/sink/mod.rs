@@ -0,0 +1,198 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+
+use crate::{ExporterOptions, Labels};
+
+/// Graphite plaintext protocol sink
+pub mod graphite;
+/// DogStatsD sink
+pub mod statsd;
+
+/// Maximum UDP datagram size safe for common network MTU
+const MAX_DATAGRAM_SIZE: usize = 1432;
+
+/// Single sample of collected metrics batch
+#[derive(Debug)]
+pub struct Sample<'a> {
+    /// Metric subsystem, e.g.: cat_indices
+    pub subsystem: &'static str,
+    /// Metric key including postfix, e.g.: store_size_bytes
+    pub key: &'a str,
+    /// Metric labels including constant labels
+    pub labels: &'a Labels,
+    /// Metric value
+    pub value: f64,
+    /// Collection timestamp in seconds since epoch
+    pub timestamp: i64,
+}
+
+/// Transport of flushed sink lines
+#[derive(Debug, Clone)]
+pub enum Transport {
+    /// Lines are packed into datagrams, e.g.: StatsD
+    Udp(String),
+    /// Lines are written to new connection on each flush, e.g.: Graphite
+    Tcp(String),
+}
+
+/// Output of collected metrics besides Prometheus registry, samples are
+/// buffered on collect and sent on flush interval
+pub trait Sink: fmt::Debug + Send + Sync {
+    /// Sink name, e.g.: statsd
+    fn name(&self) -> &'static str;
+
+    /// Buffer sample until next flush
+    fn push(&self, sample: &Sample<'_>);
+
+    /// Take buffered lines, each line is terminated with newline
+    fn take(&self) -> Vec<String>;
+
+    /// Transport of flushed lines
+    fn transport(&self) -> &Transport;
+
+    /// Interval of flushing buffered lines
+    fn flush_interval(&self) -> Duration;
+}
+
+/// Configured sinks, Prometheus registry is the default sink thus empty
+/// unless StatsD or Graphite address is set
+pub(crate) fn from_options(options: &ExporterOptions) -> Vec<Arc<dyn Sink>> {
+    let mut sinks: Vec<Arc<dyn Sink>> = Vec::new();
+
+    if let Some(ref addr) = options.exporter_statsd_addr {
+        sinks.push(Arc::new(statsd::StatsdSink::new(
+            addr.clone(),
+            options.exporter_statsd_prefix.clone(),
+            options.exporter_statsd_flush_interval,
+        )));
+    }
+
+    if let Some(ref addr) = options.exporter_graphite_addr {
+        sinks.push(Arc::new(graphite::GraphiteSink::new(
+            addr.clone(),
+            options.exporter_graphite_prefix.clone(),
+            options.exporter_graphite_flush_interval,
+        )));
+    }
+
+    sinks
+}
+
+/// Flush sink buffered lines on sink flush interval
+pub(crate) async fn flush(sink: Arc<dyn Sink>) {
+    info!(
+        "Starting sink: {} to: {:?} with flush interval: {}sec",
+        sink.name(),
+        sink.transport(),
+        sink.flush_interval().as_secs()
+    );
+
+    let mut interval = tokio::time::interval(sink.flush_interval());
+
+    loop {
+        let _ = interval.tick().await;
+
+        let lines = sink.take();
+
+        if lines.is_empty() {
+            continue;
+        }
+
+        let result = match sink.transport() {
+            Transport::Udp(addr) => send_udp(addr, &lines).await,
+            Transport::Tcp(addr) => send_tcp(addr, &lines).await,
+        };
+
+        if let Err(e) = result {
+            error!(
+                "sink {} dropping {} lines err {}",
+                sink.name(),
+                lines.len(),
+                e
+            );
+        }
+    }
+}
+
+async fn send_udp(addr: &str, lines: &[String]) -> std::io::Result<()> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(addr).await?;
+
+    for datagram in pack(lines, MAX_DATAGRAM_SIZE) {
+        let _ = socket.send(datagram.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+async fn send_tcp(addr: &str, lines: &[String]) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect(addr).await?;
+
+    for line in lines.iter() {
+        stream.write_all(line.as_bytes()).await?;
+    }
+
+    stream.shutdown().await
+}
+
+/// Pack newline terminated lines into payloads of at most `size` bytes,
+/// a line longer than `size` is sent in payload of its own
+fn pack(lines: &[String], size: usize) -> Vec<String> {
+    let mut payloads = Vec::new();
+    let mut payload = String::new();
+
+    for line in lines.iter() {
+        if !payload.is_empty() && payload.len() + line.len() > size {
+            payloads.push(std::mem::take(&mut payload));
+        }
+        payload.push_str(line);
+    }
+
+    if !payload.is_empty() {
+        payloads.push(payload);
+    }
+
+    payloads
+}
+
+/// Replace characters reserved by line protocol with underscore
+fn sanitize(input: &str, reserved: &[char]) -> String {
+    input
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || reserved.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sink_pack() {
+        let lines = vec![
+            "aaaa\n".to_string(),
+            "bbbb\n".into(),
+            "cccccccccccc\n".into(),
+        ];
+
+        assert_eq!(
+            pack(&lines, 10),
+            vec!["aaaa\nbbbb\n".to_string(), "cccccccccccc\n".into()]
+        );
+        assert_eq!(pack(&[], 10), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_sink_sanitize() {
+        assert_eq!(sanitize("a b|c,d", &['|', ',']), "a_b_c_d");
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{sanitize, Sample, Sink, Transport};
+
+/// Characters reserved by DogStatsD datagram format
+const RESERVED: &[char] = &[':', '|', '@', '#', ','];
+
+/// DogStatsD gauges with labels as tags
+///
+/// https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/
+#[derive(Debug)]
+pub struct StatsdSink {
+    transport: Transport,
+    prefix: String,
+    flush_interval: Duration,
+    /// Latest gauge value by metric name and tags, gauges report last value
+    /// thus samples of the same series are overwritten until flush
+    buffer: Mutex<BTreeMap<(String, String), f64>>,
+}
+
+impl StatsdSink {
+    /// New DogStatsD sink sending to UDP address, e.g.: 127.0.0.1:8125
+    pub fn new(addr: String, prefix: String, flush_interval: Duration) -> Self {
+        Self {
+            transport: Transport::Udp(addr),
+            prefix,
+            flush_interval,
+            buffer: Default::default(),
+        }
+    }
+
+    /// Metric name and tags of sample, e.g.:
+    /// `elasticsearch.cat_health.shards` and `#cluster:testing`
+    fn series(&self, sample: &Sample<'_>) -> (String, String) {
+        let mut name = String::new();
+
+        if !self.prefix.is_empty() {
+            name.push_str(&sanitize(&self.prefix, RESERVED));
+            name.push('.');
+        }
+        name.push_str(sample.subsystem);
+        name.push('.');
+        name.push_str(&sanitize(sample.key, RESERVED));
+
+        let mut tags = String::new();
+
+        for (index, (label, value)) in sample.labels.iter().enumerate() {
+            tags.push(if index == 0 { '#' } else { ',' });
+            tags.push_str(&sanitize(label, RESERVED));
+            tags.push(':');
+            tags.push_str(&sanitize(value, RESERVED));
+        }
+
+        (name, tags)
+    }
+}
+
+impl Sink for StatsdSink {
+    fn name(&self) -> &'static str {
+        "statsd"
+    }
+
+    fn push(&self, sample: &Sample<'_>) {
+        if !sample.value.is_finite() {
+            return;
+        }
+
+        let _ = self
+            .buffer
+            .lock()
+            .expect("statsd buffer lock")
+            .insert(self.series(sample), sample.value);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.buffer.lock().expect("statsd buffer lock"))
+            .into_iter()
+            .map(|((name, tags), value)| {
+                if tags.is_empty() {
+                    format!("{}:{}|g\n", name, value)
+                } else {
+                    format!("{}:{}|g|{}\n", name, value, tags)
+                }
+            })
+            .collect()
+    }
+
+    fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    fn flush_interval(&self) -> Duration {
+        self.flush_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Labels;
+
+    #[test]
+    fn test_statsd_sink() {
+        let sink = StatsdSink::new(
+            "127.0.0.1:8125".into(),
+            "elasticsearch".into(),
+            Duration::from_secs(10),
+        );
+
+        let mut labels = Labels::new();
+        let _ = labels.insert("cluster".into(), "testing".into());
+        let _ = labels.insert("index".into(), "logs,2024|01".into());
+
+        for value in [1.0, 2.5] {
+            sink.push(&Sample {
+                subsystem: "cat_indices",
+                key: "docs_count",
+                labels: &labels,
+                value,
+                timestamp: 1700000000,
+            });
+        }
+
+        sink.push(&Sample {
+            subsystem: "cat_health",
+            key: "shards",
+            labels: &Labels::new(),
+            value: 5.0,
+            timestamp: 1700000000,
+        });
+
+        assert_eq!(
+            sink.take(),
+            vec![
+                "elasticsearch.cat_health.shards:5|g\n".to_string(),
+                "elasticsearch.cat_indices.docs_count:2.5|g|#cluster:testing,index:logs_2024_01\n"
+                    .into(),
+            ]
+        );
+        assert!(sink.take().is_empty());
+    }
+}